@@ -1,12 +1,16 @@
 use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
 use std::path::{Path, PathBuf};
 
+fn load(file_path: &Path) -> zink::FileDescription {
+	zink::load(file_path, &mut zink::Sources::default()).unwrap()
+}
+
 fn build_file(temp_dir: &Path, file_path: &Path) -> (PathBuf, PathBuf) {
 	let optimized_path = temp_dir.join("optimized");
 	let unoptimized_path = temp_dir.join("unoptimized");
 
-	zink::build_executable(file_path, &optimized_path, true);
-	zink::build_executable(file_path, &unoptimized_path, false);
+	zink::build_executable(load(file_path), &optimized_path, true).unwrap();
+	zink::build_executable(load(file_path), &unoptimized_path, false).unwrap();
 
 	return (optimized_path, unoptimized_path);
 }
@@ -28,11 +32,11 @@ fn benchmark_entry(c: &mut Criterion) {
 		group.bench_with_input(BenchmarkId::new("native-unoptimized", file_name),
 			&unopt, |b, path| b.iter(|| test_executable(path)));
 		group.bench_with_input(BenchmarkId::new("JIT-optimized", file_name),
-			file_path, |b, path| b.iter(|| zink::run_jit(&std::fs::read_to_string(path).unwrap(), true)));
+			file_path, |b, path| b.iter(|| zink::run_jit(load(path), true).unwrap()));
 		group.bench_with_input(BenchmarkId::new("JIT-unoptimized", file_name),
-			file_path, |b, path| b.iter(|| zink::run_jit(&std::fs::read_to_string(path).unwrap(), false)));
+			file_path, |b, path| b.iter(|| zink::run_jit(load(path), false).unwrap()));
 		group.bench_with_input(BenchmarkId::new("interpreter", file_name),
-			file_path, |b, path| b.iter(|| zink::run_interpreter(&std::fs::read_to_string(path).unwrap())));
+			file_path, |b, path| b.iter(|| zink::run_interpreter(load(path)).unwrap()));
 	}
 }
 
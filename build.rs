@@ -3,5 +3,6 @@ fn main() {}
 
 #[cfg(feature = "jit")]
 fn main() {
+    println!("cargo:rerun-if-changed=zinc_std_c.c");
     cc::Build::new().file("zinc_std_c.c").compile("zinc_std_c");
 }
\ No newline at end of file
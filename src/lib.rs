@@ -1,7 +1,158 @@
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use zir::typecheck::TypeError;
+use zir::parse::ParseError;
+use zir::{Span, Import};
+pub use zir::FileDescription;
 #[cfg(feature = "native")]
 use std::io::Write;
 
+/// An error message and the part of the source it refers to.
+#[derive(Debug)]
+pub struct Diagnostic {
+	pub message: String,
+	pub span: Option<Span>
+}
+
+impl From<TypeError> for Diagnostic {
+	fn from(error: TypeError) -> Self {
+		Diagnostic {
+			message: error.to_string(),
+			span: Some(error.span)
+		}
+	}
+}
+
+impl From<ParseError> for Diagnostic {
+	fn from(error: ParseError) -> Self {
+		Diagnostic {
+			message: error.to_string(),
+			span: Some(error.span())
+		}
+	}
+}
+
+fn to_diagnostics(errors: Vec<TypeError>) -> Vec<Diagnostic> {
+	errors.into_iter().map(Diagnostic::from).collect()
+}
+
+/// A file that a program was loaded from.
+pub struct SourceFile {
+	pub name: String,
+	pub code: String,
+	// where the spans of this file start
+	pub offset: usize
+}
+
+/// Every file that a program was loaded from, which diagnostics can point into.
+#[derive(Default)]
+pub struct Sources {
+	pub files: Vec<SourceFile>
+}
+
+impl Sources {
+	fn add(&mut self, name: String, code: String) -> &SourceFile {
+		// leaves a gap after every file, so that a span at the end of one is not at the start of the next
+		let offset = self.files.last().map_or(0, |file| file.offset + file.code.len() + 1);
+
+		self.files.push(SourceFile {
+			name,
+			code,
+			offset
+		});
+		self.files.last().unwrap()
+	}
+
+	/// The file a span of the program points into, and the same span counted from the start of that file.
+	pub fn locate(&self, span: Span) -> (&SourceFile, Span) {
+		let file = self.files.iter().rev().find(|file| file.offset <= span.start).expect("span should be in a loaded file");
+
+		(file, Span {
+			start: span.start - file.offset,
+			end: span.end - file.offset
+		})
+	}
+}
+
+fn parse(file: &SourceFile) -> Result<FileDescription, Vec<Diagnostic>> {
+	zir::parse::parse_at(&file.code, file.offset).map_err(|error| vec![Diagnostic::from(error)])
+}
+
+fn read(path: &Path, span: Option<Span>) -> Result<(PathBuf, String), Vec<Diagnostic>> {
+	let error = |error: std::io::Error| vec![Diagnostic {
+		message: format!("cannot read `{}`, {}", path.display(), error),
+		span
+	}];
+
+	let canonical = path.canonicalize().map_err(error)?;
+	let code = std::fs::read_to_string(&canonical).map_err(error)?;
+	Ok((canonical, code))
+}
+
+fn merge(program: &mut FileDescription, file: FileDescription) -> Result<(), Vec<Diagnostic>> {
+	zir::parse::merge(program, file).map_err(|error| vec![Diagnostic::from(error)])
+}
+
+// merges every file in `imports` into `program` after the files it imports, so that a global is always declared after the globals it can use
+fn load_imports(imports: Vec<Import>, directory: &Path, program: &mut FileDescription, sources: &mut Sources, importing: &mut Vec<(PathBuf, String)>, loaded: &mut HashSet<PathBuf>) -> Result<(), Vec<Diagnostic>> {
+	for import in imports {
+		let path = directory.join(&import.path);
+		let (canonical, code) = read(&path, Some(import.span))?;
+
+		if let Some(position) = importing.iter().position(|(importer, _)| *importer == canonical) {
+			let mut cycle: Vec<String> = importing[position..].iter().map(|(_, name)| format!("`{}`", name)).collect();
+			cycle.push(format!("`{}`", path.display()));
+
+			return Err(vec![Diagnostic {
+				message: format!("import cycle, {}", cycle.join(" imports ")),
+				span: Some(import.span)
+			}]);
+		}
+
+		// a file that several files import is only merged once
+		if !loaded.insert(canonical.clone()) {
+			continue;
+		}
+
+		let name = path.display().to_string();
+		let mut imported = parse(sources.add(name.clone(), code))?;
+
+		importing.push((canonical, name));
+		load_imports(std::mem::take(&mut imported.imports), path.parent().unwrap(), program, sources, importing, loaded)?;
+		importing.pop();
+
+		merge(program, imported)?;
+	}
+
+	Ok(())
+}
+
+// merges the file a program is run from last, since it's the only one with statements
+fn load_main(mut main: FileDescription, directory: &Path, sources: &mut Sources, importing: &mut Vec<(PathBuf, String)>, loaded: &mut HashSet<PathBuf>) -> Result<FileDescription, Vec<Diagnostic>> {
+	let mut program = FileDescription::default();
+	load_imports(std::mem::take(&mut main.imports), directory, &mut program, sources, importing, loaded)?;
+
+	program.statements = std::mem::take(&mut main.statements);
+	merge(&mut program, main)?;
+	Ok(program)
+}
+
+/// Reads the program in `input` along with every file it imports.
+pub fn load(input: &Path, sources: &mut Sources) -> Result<FileDescription, Vec<Diagnostic>> {
+	let (canonical, code) = read(input, None)?;
+	let name = input.display().to_string();
+	let main = parse(sources.add(name.clone(), code))?;
+
+	let mut loaded = HashSet::from([canonical.clone()]);
+	load_main(main, input.parent().unwrap(), sources, &mut vec![(canonical, name)], &mut loaded)
+}
+
+/// Reads a program that isn't in a file, so its imports are resolved relative to the working directory.
+pub fn load_code(name: &str, code: &str, sources: &mut Sources) -> Result<FileDescription, Vec<Diagnostic>> {
+	let main = parse(sources.add(String::from(name), String::from(code)))?;
+	load_main(main, Path::new(""), sources, &mut vec![], &mut HashSet::new())
+}
+
 #[cfg(all(target_os = "windows", feature = "native"))]
 fn link(temp_path: &Path, output: &Path, optimize: bool) {
 	let cl = cc::windows_registry::find_tool("x86_64-msvc", "cl.exe").expect("cannot find cl");
@@ -47,12 +198,9 @@ fn link(temp_path: &Path, output: &Path, optimize: bool) {
 }
 
 #[cfg(feature = "native")]
-pub fn build_executable(input: &Path, output: &Path, optimize: bool) {
+pub fn build_executable(file_description: FileDescription, output: &Path, optimize: bool) -> Result<(), Vec<Diagnostic>> {
 	use path_absolutize::Absolutize;
 
-	let input_contents = std::fs::read_to_string(input).expect("cannot read file");
-	let file_description = zir::parse::parse(&input_contents).expect("failed to parse");
-
 	let temp_dir = tempfile::tempdir().expect("cannot create temporary directory");
 	let temp_path = temp_dir.path();
 
@@ -68,59 +216,104 @@ pub fn build_executable(input: &Path, output: &Path, optimize: bool) {
 		entry_file.write_all(include_bytes!("../zinc_entry_c.c")).expect("cannot write to entry file");
 	}
 
-	zir::build_object(file_description, temp_path.join("zir_obj.o"), optimize);
+	zir::build_object(file_description, temp_path.join("zir_obj.o"), optimize).map_err(to_diagnostics)?;
 	link(temp_path, &output.absolutize().unwrap(), optimize);
+
+	Ok(())
 }
 
 #[cfg(not(feature = "native"))]
-pub fn build_executable(_input: &Path, _output: &Path, _optimize: bool) {
+pub fn build_executable(_file_description: FileDescription, _output: &Path, _optimize: bool) -> Result<(), Vec<Diagnostic>> {
 	panic!("native feature not enabled");
 }
 
 #[cfg(feature = "jit")]
-extern {
+extern "C" {
 	fn print_int(x: i32);
+	fn print_i8(x: i8);
+	fn print_i16(x: i16);
+	fn print_i64(x: i64);
+	fn print_u8(x: u8);
+	fn print_u16(x: u16);
+	fn print_u32(x: u32);
+	fn print_u64(x: u64);
+	fn print_float(x: f64);
 	fn print_bool(x: bool);
 	fn print_str(str: *const u8);
 	fn str_eq(lhs: *const u8, rhs: *const u8) -> bool;
 	fn str_len(str: *const u8) -> i32;
 	fn str_concat(lhs: *const u8, rhs: *const u8) -> *const u8;
+	fn i64_to_str(x: i64) -> *const u8;
+	fn u64_to_str(x: u64) -> *const u8;
+	fn float_to_str(x: f64) -> *const u8;
+	fn bool_to_str(x: bool) -> *const u8;
 	fn assert_int_eq(lhs: i32, rhs: i32);
 	fn assert_bool_eq(lhs: i32, rhs: i32);
 	fn assert_str_eq(lhs: *const u8, rhs: *const u8);
 	fn panic();
+	fn zinc_array_new(length: i32) -> *const u8;
+	fn zinc_array_slot(array: *const u8, index: i32) -> *const u8;
+	fn zinc_array_len(array: *const u8) -> i32;
+	fn zinc_array_push(array: *const u8) -> *const u8;
+	fn zinc_struct_new(fields: i32) -> *const u8;
 }
 
 #[cfg(feature = "jit")]
-pub fn run_jit(code: &str, optimize: bool) {
-	let file_description = zir::parse::parse(code).expect("failed to parse");
+pub fn run_jit(file_description: FileDescription, optimize: bool) -> Result<(), Vec<Diagnostic>> {
 	let symbols = vec![
 		("print_int", print_int as *const u8),
+		("print_i8", print_i8 as *const u8),
+		("print_i16", print_i16 as *const u8),
+		("print_i64", print_i64 as *const u8),
+		("print_u8", print_u8 as *const u8),
+		("print_u16", print_u16 as *const u8),
+		("print_u32", print_u32 as *const u8),
+		("print_u64", print_u64 as *const u8),
+		("print_float", print_float as *const u8),
 		("print_bool", print_bool as *const u8),
 		("print_str", print_str as *const u8),
 		("str_eq", str_eq as *const u8),
 		("str_len", str_len as *const u8),
 		("str_concat", str_concat as *const u8),
+		("i64_to_str", i64_to_str as *const u8),
+		("u64_to_str", u64_to_str as *const u8),
+		("float_to_str", float_to_str as *const u8),
+		("bool_to_str", bool_to_str as *const u8),
 		("assert_int_eq", assert_int_eq as *const u8),
 		("assert_bool_eq", assert_bool_eq as *const u8),
 		("assert_str_eq", assert_str_eq as *const u8),
-		("panic", panic as *const u8)
+		("panic", panic as *const u8),
+		("zinc_array_new", zinc_array_new as *const u8),
+		("zinc_array_slot", zinc_array_slot as *const u8),
+		("zinc_array_len", zinc_array_len as *const u8),
+		("zinc_array_push", zinc_array_push as *const u8),
+		("zinc_struct_new", zinc_struct_new as *const u8)
 	];
-	unsafe { zir::run_jit(file_description, optimize, symbols); };
+	unsafe { zir::run_jit(file_description, optimize, symbols) }.map_err(to_diagnostics)
 }
 
 #[cfg(not(feature = "jit"))]
-pub fn run_jit(_code: &str, _optimize: bool) {
+pub fn run_jit(_file_description: FileDescription, _optimize: bool) -> Result<(), Vec<Diagnostic>> {
 	panic!("jit feature not enabled");
 }
 
 #[cfg(feature = "interpreter")]
-pub fn run_interpreter(code: &str) -> String {
-	let file_description = zir::parse::parse(code).expect("failed to parse");
-	zir::interpreter::interpret(file_description).unwrap()
+pub fn run_interpreter(file_description: FileDescription) -> Result<String, Vec<Diagnostic>> {
+	use zir::interpreter::RuntimeResult;
+
+	match zir::interpreter::interpret(file_description) {
+		Ok(output) => Ok(output),
+		Err(err) => match err.result {
+			RuntimeResult::InvalidProgram(errors) => Err(to_diagnostics(errors)),
+			_ => Err(vec![Diagnostic {
+				message: err.to_string(),
+				span: err.span
+			}])
+		}
+	}
 }
 
 #[cfg(not(feature = "interpreter"))]
-pub fn run_interpreter(_code: &str) -> String {
+pub fn run_interpreter(_file_description: FileDescription) -> Result<String, Vec<Diagnostic>> {
 	panic!("interpreter feature not enabled");
 }
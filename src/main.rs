@@ -1,5 +1,6 @@
 use clap::{Parser, Subcommand};
-use std::path::{PathBuf, Path};
+use std::path::PathBuf;
+use zink::{Diagnostic, Sources};
 
 #[derive(Parser)]
 #[clap(author, version)]
@@ -41,39 +42,76 @@ enum Commands {
 }
 
 #[cfg(target_os = "windows")]
-const DEFAULT_FILE_NAME: &'static str = "bruh.exe";
+const DEFAULT_FILE_NAME: &str = "bruh.exe";
 
 #[cfg(not(target_os = "windows"))]
-const DEFAULT_FILE_NAME: &'static str = "bruh";
+const DEFAULT_FILE_NAME: &str = "bruh";
+
+// renders a diagnostic in the style of rustc, with the offending source underlined
+fn render(sources: &Sources, diagnostic: &Diagnostic) -> String {
+	let mut rendered = format!("error: {}\n", diagnostic.message);
+
+	let span = match diagnostic.span {
+		Some(span) => span,
+		None => return rendered
+	};
+
+	let (file, span) = sources.locate(span);
+	let source = &file.code[..];
+
+	let (line, column) = span.line_col(source);
+	let line_text = source.lines().nth(line - 1).unwrap_or("");
+	let gutter = " ".repeat(line.to_string().len());
+
+	// only the first line of a multi-line span is underlined
+	let underline_len = source[span.start..span.end].lines().next().map(|text| text.chars().count()).unwrap_or(0).max(1);
+
+	rendered.push_str(&format!("{}--> {}:{}:{}\n", gutter, file.name, line, column));
+	rendered.push_str(&format!("{} |\n", gutter));
+	rendered.push_str(&format!("{} | {}\n", line, line_text));
+	rendered.push_str(&format!("{} | {}{}\n", gutter, " ".repeat(column - 1), "^".repeat(underline_len)));
+	rendered
+}
+
+fn report<T>(sources: &Sources, result: Result<T, Vec<Diagnostic>>) -> T {
+	match result {
+		Ok(val) => val,
+		Err(diagnostics) => {
+			for diagnostic in diagnostics {
+				eprintln!("{}", render(sources, &diagnostic));
+			}
+			std::process::exit(1);
+		}
+	}
+}
+
+const EXEC_FILE_NAME: &str = "<exec>";
 
 fn main() {
 	let args = Args::parse();
+	let mut sources = Sources::default();
 
 	match args.command {
 		Commands::Build { input_file, output_file, optimize } => {
-			match output_file {
-				Some(output_path) => {
-					zink::build_executable(&input_file, &output_path, optimize);
-				},
-				None => {
-					let output_path = Path::new(DEFAULT_FILE_NAME);
-					zink::build_executable(&input_file, output_path, optimize);
-				}
-			}
+			let output_path = output_file.unwrap_or_else(|| PathBuf::from(DEFAULT_FILE_NAME));
+			let result = zink::load(&input_file, &mut sources).and_then(|program| zink::build_executable(program, &output_path, optimize));
+			report(&sources, result);
 		},
 		Commands::Run { input_file, optimize } => {
-			let code = std::fs::read_to_string(input_file).expect("cannot read file");
-			zink::run_jit(&code, optimize);
+			let result = zink::load(&input_file, &mut sources).and_then(|program| zink::run_jit(program, optimize));
+			report(&sources, result);
 		},
 		Commands::Exec { code, optimize } => {
-			zink::run_jit(&code, optimize);
+			let result = zink::load_code(EXEC_FILE_NAME, &code, &mut sources).and_then(|program| zink::run_jit(program, optimize));
+			report(&sources, result);
 		},
 		Commands::RunSafe { input_file } => {
-			let code = std::fs::read_to_string(input_file).expect("cannot read file");
-			print!("{}", zink::run_interpreter(&code));
+			let result = zink::load(&input_file, &mut sources).and_then(zink::run_interpreter);
+			print!("{}", report(&sources, result));
 		}
 		Commands::ExecSafe { code } => {
-			print!("{}", zink::run_interpreter(&code));
+			let result = zink::load_code(EXEC_FILE_NAME, &code, &mut sources).and_then(zink::run_interpreter);
+			print!("{}", report(&sources, result));
 		}
 	}
 }
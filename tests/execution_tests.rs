@@ -1,5 +1,9 @@
 use std::path::Path;
 
+fn load(file_path: &Path) -> zink::FileDescription {
+	zink::load(file_path, &mut zink::Sources::default()).expect("failed to load")
+}
+
 #[cfg(feature = "native")]
 fn test_native(file_path: &Path, optimize: bool) {
 	use std::io::Write;
@@ -7,7 +11,7 @@ fn test_native(file_path: &Path, optimize: bool) {
 	let temp_dir = tempfile::tempdir().expect("cannot create temporary directory");
 	let executable_path = temp_dir.path().join("test");
 
-	zink::build_executable(file_path, &executable_path, optimize);
+	zink::build_executable(load(file_path), &executable_path, optimize).expect("failed to typecheck");
 
 	let output = std::process::Command::new(executable_path).output().unwrap();
 	std::io::stdout().write_all(&output.stdout).unwrap();
@@ -21,12 +25,10 @@ fn test_native(_file_path: &Path, _optimize: bool) {
 
 #[cfg(feature = "jit")]
 fn test_jit(file_path: &Path) {
-	let code = std::fs::read_to_string(file_path).unwrap();
-
 	println!("testing unoptimized JIT");
-	zink::run_jit(&code, false);
+	zink::run_jit(load(file_path), false).expect("failed to typecheck");
 	println!("testing optimized JIT");
-	zink::run_jit(&code, true);
+	zink::run_jit(load(file_path), true).expect("failed to typecheck");
 }
 
 #[cfg(not(feature = "jit"))]
@@ -36,8 +38,7 @@ fn test_jit(_file_path: &Path) {
 
 #[cfg(feature = "interpreter")]
 fn test_interpreter(file_path: &Path) {
-	let code = std::fs::read_to_string(file_path).unwrap();
-	zink::run_interpreter(&code);
+	zink::run_interpreter(load(file_path)).expect("failed to typecheck");
 }
 
 #[cfg(not(feature = "interpreter"))]
@@ -56,31 +57,96 @@ fn test_file(file_path: &Path) {
 	test_interpreter(file_path);
 }
 
+#[test]
+fn arrays() {
+	test_file(Path::new("./tests/arrays.zn"));
+}
+
+#[test]
+fn bitwise() {
+	test_file(Path::new("./tests/bitwise.zn"));
+}
+
 #[test]
 fn booleans() {
 	test_file(Path::new("./tests/booleans.zn"));
 }
 
+#[test]
+fn break_continue() {
+	test_file(Path::new("./tests/break_continue.zn"));
+}
+
+#[test]
+fn closures() {
+	test_file(Path::new("./tests/closures.zn"));
+}
+
 #[test]
 fn conditionals() {
 	test_file(Path::new("./tests/conditionals.zn"));
 }
 
+#[test]
+fn enums() {
+	test_file(Path::new("./tests/enums.zn"));
+}
+
+#[test]
+fn floats() {
+	test_file(Path::new("./tests/floats.zn"));
+}
+
+#[test]
+fn for_loop() {
+	test_file(Path::new("./tests/for_loop.zn"));
+}
+
+#[test]
+fn function_values() {
+	test_file(Path::new("./tests/function_values.zn"));
+}
+
 #[test]
 fn functions() {
 	test_file(Path::new("./tests/functions.zn"));
 }
 
+#[test]
+fn globals() {
+	test_file(Path::new("./tests/globals.zn"));
+}
+
+#[test]
+fn imports() {
+	test_file(Path::new("./tests/imports.zn"));
+}
+
 #[test]
 fn infinite_loop() {
 	test_file(Path::new("./tests/infinite_loop.zn"));
 }
 
+#[test]
+fn integer_types() {
+	test_file(Path::new("./tests/integer_types.zn"));
+}
+
 #[test]
 fn integers() {
 	test_file(Path::new("./tests/integers.zn"));
 }
 
+#[test]
+fn interpolation() {
+	test_file(Path::new("./tests/interpolation.zn"));
+}
+
+#[test]
+fn logical_operators() {
+	test_file(Path::new("./tests/logical_operators.zn"));
+}
+
 #[test]
 fn nested_while() {
 	test_file(Path::new("./tests/nested_while.zn"));
@@ -91,16 +157,46 @@ fn recursion() {
 	test_file(Path::new("./tests/recursion.zn"));
 }
 
+#[test]
+fn scoping() {
+	test_file(Path::new("./tests/scoping.zn"));
+}
+
 #[test]
 fn strings() {
 	test_file(Path::new("./tests/strings.zn"));
 }
 
+#[test]
+fn structs() {
+	test_file(Path::new("./tests/structs.zn"));
+}
+
+#[test]
+fn type_inference() {
+	test_file(Path::new("./tests/type_inference.zn"));
+}
+
+#[test]
+fn unary() {
+	test_file(Path::new("./tests/unary.zn"));
+}
+
+#[test]
+fn unsigned_division() {
+	test_file(Path::new("./tests/unsigned_division.zn"));
+}
+
 #[test]
 fn variables() {
 	test_file(Path::new("./tests/variables.zn"));
 }
 
+#[test]
+fn void_functions() {
+	test_file(Path::new("./tests/void_functions.zn"));
+}
+
 #[test]
 fn while_loop() {
 	test_file(Path::new("./tests/while_loop.zn"));
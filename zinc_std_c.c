@@ -2,11 +2,57 @@
 #include <stdbool.h>
 #include <stdlib.h>
 #include <string.h>
+#include <stdint.h>
+#include <inttypes.h>
 
 void print_int(int x) {
 	printf("%d\n", x);
 }
 
+void print_i8(int8_t x) {
+	printf("%" PRId8 "\n", x);
+}
+
+void print_i16(int16_t x) {
+	printf("%" PRId16 "\n", x);
+}
+
+void print_i64(int64_t x) {
+	printf("%" PRId64 "\n", x);
+}
+
+void print_u8(uint8_t x) {
+	printf("%" PRIu8 "\n", x);
+}
+
+void print_u16(uint16_t x) {
+	printf("%" PRIu16 "\n", x);
+}
+
+void print_u32(uint32_t x) {
+	printf("%" PRIu32 "\n", x);
+}
+
+void print_u64(uint64_t x) {
+	printf("%" PRIu64 "\n", x);
+}
+
+// writes the fewest significant digits that read back as the same value
+static void format_float(char *buffer, size_t size, double x) {
+	for (int precision = 6; precision <= 17; precision++) {
+		snprintf(buffer, size, "%.*g", precision, x);
+		if (strtod(buffer, NULL) == x) {
+			break;
+		}
+	}
+}
+
+void print_float(double x) {
+	char buffer[32];
+	format_float(buffer, sizeof(buffer), x);
+	puts(buffer);
+}
+
 void print_bool(bool x) {
 	if (x) {
 		puts("true");
@@ -37,6 +83,33 @@ char* str_concat(char *lhs, char *rhs) {
 	return new_str;
 }
 
+// like str_concat, the strings these return are never freed
+char* i64_to_str(int64_t x) {
+	char *str = malloc(21);
+	snprintf(str, 21, "%" PRId64, x);
+	return str;
+}
+
+char* u64_to_str(uint64_t x) {
+	char *str = malloc(21);
+	snprintf(str, 21, "%" PRIu64, x);
+	return str;
+}
+
+char* float_to_str(double x) {
+	char *str = malloc(32);
+	format_float(str, 32, x);
+	return str;
+}
+
+char* bool_to_str(bool x) {
+	if (x) {
+		return "true";
+	} else {
+		return "false";
+	}
+}
+
 void assert_int_eq(int lhs, int rhs) {
 	if (lhs != rhs) {
 		printf("assertion failed, %d != %d\n", lhs, rhs);
@@ -62,3 +135,45 @@ void panic() {
 	puts("panic");
 	exit(1);
 }
+
+// arrays and structs are never freed, just like strings from str_concat
+struct zinc_array {
+	int32_t length;
+	int32_t capacity;
+	// every element gets 8 bytes, which is enough to hold any zinc value
+	int64_t *elements;
+};
+
+struct zinc_array *zinc_array_new(int32_t length) {
+	struct zinc_array *array = malloc(sizeof(struct zinc_array));
+	array->length = length;
+	array->capacity = length;
+	array->elements = calloc(length, sizeof(int64_t));
+	return array;
+}
+
+int64_t *zinc_array_slot(struct zinc_array *array, int32_t index) {
+	if (index < 0 || index >= array->length) {
+		printf("index out of bounds: the length is %d but the index is %d\n", array->length, index);
+		exit(1);
+	}
+	return &array->elements[index];
+}
+
+int32_t zinc_array_len(struct zinc_array *array) {
+	return array->length;
+}
+
+// grows the array by one element and returns the slot for it
+int64_t *zinc_array_push(struct zinc_array *array) {
+	if (array->length == array->capacity) {
+		array->capacity = array->capacity == 0 ? 4 : array->capacity * 2;
+		array->elements = realloc(array->elements, array->capacity * sizeof(int64_t));
+	}
+	return &array->elements[array->length++];
+}
+
+// structs get an 8 byte slot for every field, like array elements
+int64_t *zinc_struct_new(int32_t fields) {
+	return calloc(fields, sizeof(int64_t));
+}
@@ -1,26 +1,30 @@
-use cranelift_codegen::ir::{Signature, Function, UserFuncName, InstBuilder};
+use cranelift_codegen::ir::{Signature, Function, UserFuncName, InstBuilder, TrapCode};
 use cranelift_frontend::{FunctionBuilderContext, FunctionBuilder};
-use cranelift_module::Module;
+use cranelift_module::{Module, FuncId};
 
-use crate::node::{Statement, Definition};
+use crate::node::{AbstractType, FunctionInfo, TypeMap};
 use crate::buildnode::BuildState;
 use crate::stdlib::FuncMap;
 
-pub fn build_func<M: Module>(sig: Signature, module: &mut M, func_map: &mut FuncMap, statements: Vec<Statement>, args: &Vec<Definition>, data_index: &mut u64) -> Function {
+pub fn build_func<M: Module>(id: FuncId, sig: Signature, module: &mut M, func_map: &FuncMap, types: &TypeMap, function_info: FunctionInfo, data_index: &mut u64) -> Function {
 	let mut fn_builder_ctx = FunctionBuilderContext::new();
 
-	let mut func = Function::with_name_signature(UserFuncName::user(0, func_map.len() as u32), sig);
+	let mut func = Function::with_name_signature(UserFuncName::user(0, id.as_u32()), sig);
 	let mut builder = FunctionBuilder::new(&mut func, &mut fn_builder_ctx);
 
 	let main_block = builder.create_block();
 	builder.append_block_params_for_function_params(main_block);
 	builder.switch_to_block(main_block);
 
-	let mut build_state = BuildState::new(&mut builder, &func_map, module, args, data_index);
+	let mut build_state = BuildState::new(&mut builder, func_map, types, module, &function_info.args, &function_info.captures, data_index);
 
-	build_state.build_statements(statements);
+	build_state.build_statements(function_info.body);
 	if !builder.is_filled() {
-		builder.ins().return_(&[]);
+		// the typecheck pass makes sure that only void functions can reach their end
+		match function_info.return_type {
+			AbstractType::Void => builder.ins().return_(&[]),
+			_ => builder.ins().trap(TrapCode::UnreachableCodeReached)
+		};
 	}
 
 	builder.seal_all_blocks();
@@ -31,3 +35,26 @@ pub fn build_func<M: Module>(sig: Signature, module: &mut M, func_map: &mut Func
 
 	return func;
 }
+
+// lets a named function be called like a closure, by taking an environment and ignoring it
+pub fn build_thunk<M: Module>(sig: Signature, module: &mut M, target: FuncId) -> Function {
+	let mut fn_builder_ctx = FunctionBuilderContext::new();
+
+	let mut func = Function::with_name_signature(UserFuncName::user(1, target.as_u32()), sig);
+	let mut builder = FunctionBuilder::new(&mut func, &mut fn_builder_ctx);
+
+	let main_block = builder.create_block();
+	builder.append_block_params_for_function_params(main_block);
+	builder.switch_to_block(main_block);
+
+	let args = builder.block_params(main_block)[1..].to_vec();
+	let callee = module.declare_func_in_func(target, builder.func);
+	let call = builder.ins().call(callee, &args);
+	let results = builder.inst_results(call).to_vec();
+	builder.ins().return_(&results);
+
+	builder.seal_all_blocks();
+	builder.finalize();
+
+	return func;
+}
@@ -1,41 +1,99 @@
-use cranelift_codegen::ir::{InstBuilder, Value, immediates::Imm64, entities::FuncRef, condcodes::IntCC, types::*};
+use cranelift_codegen::ir::{InstBuilder, AbiParam, Signature, Value, Block, TrapCode, MemFlags, JumpTableData, immediates::Imm64, entities::FuncRef, condcodes::{IntCC, FloatCC}, types::*};
 use cranelift_frontend::{FunctionBuilder, Variable};
-use cranelift_module::{Module, DataContext, Linkage};
+use cranelift_module::{Module, DataContext, Linkage, FuncId, FuncOrDataId};
 use crate::stdlib::FuncMap;
-use crate::node::{Node, Statement, Definition, AbstractType};
+use crate::node::{Node, NodeKind, Statement, StatementKind, Definition, AbstractType, IntegerType, StructInfo, EnumInfo, TypeInfo, TypeMap, Pattern, MatchArm};
+
+pub fn integer_type(int_type: IntegerType) -> Type {
+	match int_type.bits() {
+		8 => I8,
+		16 => I16,
+		32 => I32,
+		_ => I64
+	}
+}
 
 pub fn deabstract<M: Module>(abstract_type: &AbstractType, object_module: &M) -> Option<Type> {
 	match abstract_type {
-		AbstractType::Integer => Some(I32),
+		AbstractType::Integer(int_type) => Some(integer_type(*int_type)),
+		AbstractType::Float => Some(F64),
 		AbstractType::Boolean => Some(B1),
-		AbstractType::String => Some(object_module.target_config().pointer_type()),
+		AbstractType::String | AbstractType::Array(_) | AbstractType::Named(_) | AbstractType::Function { .. } => Some(object_module.target_config().pointer_type()),
 		AbstractType::Void => None,
 	}
 }
 
+/// Function values are closures, which take a pointer to their environment before their arguments.
+pub fn make_signature<M: Module>(module: &M, args: &[AbstractType], return_type: &AbstractType, environment: bool) -> Signature {
+	let mut sig = module.make_signature();
+	if environment {
+		sig.params.push(AbiParam::new(module.target_config().pointer_type()));
+	}
+	for arg in args {
+		sig.params.push(AbiParam::new(deabstract(arg, module).expect("argument cannot be void")));
+	}
+	if let Some(return_type) = deabstract(return_type, module) {
+		sig.returns.push(AbiParam::new(return_type));
+	}
+
+	return sig;
+}
+
+fn function_type(data_type: &AbstractType) -> (&Vec<AbstractType>, &AbstractType) {
+	match data_type {
+		AbstractType::Function { args, return_type } => (args, return_type),
+		_ => unreachable!("node should be a function")
+	}
+}
+
+struct LoopBlocks {
+	label: Option<String>,
+	continue_block: Block,
+	after_block: Block,
+	// an infinite loop only falls through to its after block if something breaks out of it
+	broken: bool
+}
+
 pub struct BuildState<'a, 'b, M: Module> {
 	pub builder: &'a mut FunctionBuilder<'b>,
 	func_map: &'a FuncMap,
+	types: &'a TypeMap,
 	module: &'a mut M,
-	variable_list: std::collections::HashMap::<String, Variable>,
+	// innermost scope last
+	scopes: Vec<std::collections::HashMap::<String, Variable>>,
+	// innermost loop last
+	loops: Vec<LoopBlocks>,
 	variable_count: u32,
 	data_index: &'a mut u64
 }
 
 impl<'a, 'b, M: Module> BuildState<'a, 'b, M> {
-	pub fn new(builder: &'a mut FunctionBuilder<'b>, func_map: &'a FuncMap, module: &'a mut M, args: &Vec<Definition>, data_index: &'a mut u64) -> Self {
+	pub fn new(builder: &'a mut FunctionBuilder<'b>, func_map: &'a FuncMap, types: &'a TypeMap, module: &'a mut M, args: &[Definition], captures: &Option<Vec<Definition>>, data_index: &'a mut u64) -> Self {
 		let current_block = builder.current_block().unwrap();
-		let block_args = builder.block_params(current_block).to_vec();
+		let mut block_args = builder.block_params(current_block).to_vec();
 
 		let mut inst = Self {
 			builder,
 			func_map,
+			types,
 			module,
-			variable_list: std::collections::HashMap::new(),
+			scopes: vec![std::collections::HashMap::new()],
+			loops: vec![],
 			variable_count: 0,
 			data_index
 		};
 
+		// a closure copies the variables it captured out of its environment
+		if let Some(captures) = captures {
+			let environment = block_args.remove(0);
+			for (i, capture) in captures.iter().enumerate() {
+				let value = inst.build_slot_load(&capture.data_type, environment, Self::payload_offset(i));
+				let data_type = deabstract(&capture.data_type, &inst.module).expect("variable type cannot be void");
+				let var = inst.get_new_variable(capture.name.clone(), data_type);
+				inst.builder.def_var(var, value);
+			}
+		}
+
 		for (arg, block_arg) in args.iter().zip(block_args) {
 			let data_type = deabstract(&arg.data_type, &inst.module).expect("argument cannot be void");
 			let var = inst.get_new_variable(arg.name.clone(), data_type);
@@ -45,95 +103,426 @@ impl<'a, 'b, M: Module> BuildState<'a, 'b, M> {
 		return inst;
 	}
 
+	// always creates a fresh variable, shadowing any previous one with the same name
 	pub fn get_new_variable(&mut self, name: String, var_type: Type) -> Variable {
-		*self.variable_list.entry(name).or_insert_with(|| {
-			let var = Variable::with_u32(self.variable_count);
-			self.variable_count += 1;
-			self.builder.declare_var(var, var_type);
-			return var;
-		})
+		let var = self.get_hidden_variable(var_type);
+		self.scopes.last_mut().unwrap().insert(name, var);
+		return var;
+	}
+
+	// a variable that cannot be referred to by name
+	fn get_hidden_variable(&mut self, var_type: Type) -> Variable {
+		let var = Variable::with_u32(self.variable_count);
+		self.variable_count += 1;
+		self.builder.declare_var(var, var_type);
+		return var;
 	}
 
 	fn get_declared_variable(&mut self, name: String) -> Option<Variable> {
-		self.variable_list.get(&name).map(|var| *var)
+		self.scopes.iter().rev().find_map(|scope| scope.get(&name).map(|var| *var))
+	}
+
+	fn build_block(&mut self, statements: Vec<Statement>) {
+		self.scopes.push(std::collections::HashMap::new());
+		self.build_statements(statements);
+		self.scopes.pop();
+	}
+
+	// cranelift masks shift amounts to the type's width, so trap on anything outside 0..bits instead
+	fn build_shift_amount(&mut self, node: &Node) -> Value {
+		let amount = self.build_node(node);
+		let bits = node.integer_type().bits();
+		let over_wide = self.builder.ins().icmp_imm(IntCC::UnsignedGreaterThanOrEqual, amount, bits as i64);
+		self.builder.ins().trapnz(over_wide, TrapCode::IntegerOverflow);
+		return amount;
+	}
+
+	fn build_comparison(&mut self, lhs: &Node, rhs: &Node, signed: IntCC, unsigned: IntCC, float: FloatCC) -> Value {
+		let lv = self.build_node(lhs);
+		let rv = self.build_node(rhs);
+
+		match lhs.data_type() {
+			AbstractType::Float => self.builder.ins().fcmp(float, lv, rv),
+			_ if lhs.integer_type().is_signed() => self.builder.ins().icmp(signed, lv, rv),
+			_ => self.builder.ins().icmp(unsigned, lv, rv)
+		}
+	}
+
+	fn build_int_to_float(&mut self, val: Value, from: IntegerType) -> Value {
+		// cranelift only converts from 32 and 64 bit integers, and only u64 needs the unsigned conversion
+		match (from.is_signed(), from.bits()) {
+			(true, 8 | 16) => {
+				let extended = self.builder.ins().sextend(I32, val);
+				self.builder.ins().fcvt_from_sint(F64, extended)
+			},
+			(true, _) => self.builder.ins().fcvt_from_sint(F64, val),
+			(false, 64) => self.builder.ins().fcvt_from_uint(F64, val),
+			(false, _) => {
+				let extended = self.builder.ins().uextend(I64, val);
+				self.builder.ins().fcvt_from_sint(F64, extended)
+			}
+		}
+	}
+
+	// saturates at the bounds of the target type and turns NaN into 0, like rust's `as`
+	fn build_float_to_int(&mut self, val: Value, to: IntegerType) -> Value {
+		match (to.is_signed(), to.bits()) {
+			(true, 32 | 64) => self.builder.ins().fcvt_to_sint_sat(integer_type(to), val),
+			(false, 32 | 64) => self.builder.ins().fcvt_to_uint_sat(integer_type(to), val),
+			_ => {
+				let wide = self.builder.ins().fcvt_to_sint_sat(I32, val);
+
+				let min = self.builder.ins().iconst(I32, Imm64::new(to.min() as i64));
+				let below = self.builder.ins().icmp_imm(IntCC::SignedLessThan, wide, to.min() as i64);
+				let clamped = self.builder.ins().select(below, min, wide);
+
+				let max = self.builder.ins().iconst(I32, Imm64::new(to.max() as i64));
+				let above = self.builder.ins().icmp_imm(IntCC::SignedGreaterThan, clamped, to.max() as i64);
+				let clamped = self.builder.ins().select(above, max, clamped);
+
+				self.builder.ins().ireduce(integer_type(to), clamped)
+			}
+		}
+	}
+
+	fn build_call(&mut self, name: &str, args: &[Value]) -> Option<Value> {
+		let imported_func: FuncRef = self.module.declare_func_in_func(
+			self.func_map[name],
+			self.builder.func
+		);
+
+		let function_result = self.builder.ins().call(imported_func, args);
+		return self.builder.inst_results(function_result).first().copied();
+	}
+
+	// converts a part of an interpolated string to a string, widening integers so that two functions can format all of them
+	fn build_formatted(&mut self, node: &Node) -> Value {
+		let value = self.build_node(node);
+
+		match node.data_type() {
+			AbstractType::String => value,
+			AbstractType::Integer(int_type) => {
+				let (name, wide) = match (int_type.is_signed(), int_type.bits()) {
+					(true, 64) => ("i64_to_str", value),
+					(true, _) => ("i64_to_str", self.builder.ins().sextend(I64, value)),
+					(false, 64) => ("u64_to_str", value),
+					(false, _) => ("u64_to_str", self.builder.ins().uextend(I64, value))
+				};
+				self.build_call(name, &[wide]).unwrap()
+			},
+			AbstractType::Float => self.build_call("float_to_str", &[value]).unwrap(),
+			AbstractType::Boolean => self.build_call("bool_to_str", &[value]).unwrap(),
+			_ => unreachable!("the typecheck pass only allows strings, numbers and bools in interpolated strings")
+		}
+	}
+
+	// the signature of a closure comes from the types at the call site, which the typecheck pass made sure match
+	fn build_indirect_call(&mut self, closure: Value, args: &[Node], arg_values: &[Value], return_type: &AbstractType) -> Option<Value> {
+		let arg_types: Vec<AbstractType> = args.iter().map(|arg| arg.data_type().clone()).collect();
+		let sig = make_signature(self.module, &arg_types, return_type, true);
+		let sig_ref = self.builder.import_signature(sig);
+
+		let pointer_type = self.module.target_config().pointer_type();
+		let code = self.builder.ins().load(pointer_type, MemFlags::trusted(), closure, 0);
+		let call_args: Vec<Value> = std::iter::once(closure).chain(arg_values.iter().copied()).collect();
+
+		let function_result = self.builder.ins().call_indirect(sig_ref, code, &call_args);
+		return self.builder.inst_results(function_result).first().copied();
+	}
+
+	// closures are laid out like enums, with the address of their code in the first slot and the captured values after it
+	fn build_closure(&mut self, code: FuncId, captures: &[(AbstractType, Value)]) -> Value {
+		let func_ref = self.module.declare_func_in_func(code, self.builder.func);
+		let pointer_type = self.module.target_config().pointer_type();
+		let address = self.builder.ins().func_addr(pointer_type, func_ref);
+
+		let slot_count = self.builder.ins().iconst(I32, captures.len() as i64 + 1);
+		let closure = self.build_call("zinc_struct_new", &[slot_count]).unwrap();
+		self.builder.ins().store(MemFlags::trusted(), address, closure, 0);
+		for (i, (data_type, value)) in captures.iter().enumerate() {
+			self.build_slot_store(data_type, closure, Self::payload_offset(i), *value);
+		}
+
+		return closure;
+	}
+
+	// a named function becomes a closure that captures nothing, through a thunk that is built the first time it is needed
+	fn build_function_value(&mut self, name: &str, data_type: &AbstractType) -> Value {
+		let thunk_name = format!("thunk.{}", name);
+		let thunk = match self.module.get_name(&thunk_name) {
+			Some(FuncOrDataId::Func(thunk)) => thunk,
+			_ => {
+				let (args, return_type) = function_type(data_type);
+				let sig = make_signature(self.module, args, return_type, true);
+				let thunk = self.module.declare_function(&thunk_name, Linkage::Local, &sig).unwrap();
+
+				let func = crate::buildfunc::build_thunk(sig, self.module, self.func_map[name]);
+				let mut context = cranelift_codegen::Context::for_function(func);
+				self.module.define_function(thunk, &mut context).unwrap();
+				thunk
+			}
+		};
+
+		return self.build_closure(thunk, &[]);
+	}
+
+	// statics are shared by every function, so they are kept in memory rather than in variables
+	fn static_address(&mut self, name: &str) -> Option<Value> {
+		let data_id = match self.module.get_name(&format!("static.{}", name)) {
+			Some(FuncOrDataId::Data(data_id)) => data_id,
+			_ => return None
+		};
+
+		let pointer_type = self.module.target_config().pointer_type();
+		let global_value = self.module.declare_data_in_func(data_id, self.builder.func);
+		return Some(self.builder.ins().symbol_value(pointer_type, global_value));
+	}
+
+	// every array element has an 8 byte slot in the runtime, which is enough for any type
+	fn build_element_slot(&mut self, array: Value, index: Value) -> Value {
+		return self.build_call("zinc_array_slot", &[array, index]).unwrap();
+	}
+
+	fn build_slot_load(&mut self, data_type: &AbstractType, pointer: Value, offset: i32) -> Value {
+		match data_type {
+			// b1 can't be stored in memory, so booleans are kept as a byte
+			AbstractType::Boolean => {
+				let byte = self.builder.ins().load(I8, MemFlags::trusted(), pointer, offset);
+				self.builder.ins().icmp_imm(IntCC::NotEqual, byte, 0)
+			},
+			_ => {
+				let data_type = deabstract(data_type, self.module).expect("values in memory cannot be void");
+				self.builder.ins().load(data_type, MemFlags::trusted(), pointer, offset)
+			}
+		}
+	}
+
+	fn build_slot_store(&mut self, data_type: &AbstractType, pointer: Value, offset: i32, value: Value) {
+		let value = match data_type {
+			AbstractType::Boolean => self.builder.ins().bint(I8, value),
+			_ => value
+		};
+		self.builder.ins().store(MemFlags::trusted(), value, pointer, offset);
+	}
+
+	fn struct_info(&self, name: &str) -> &'a StructInfo {
+		match &self.types[name] {
+			TypeInfo::Struct(info) => info,
+			_ => unreachable!("type should be a struct")
+		}
+	}
+
+	fn enum_info(&self, name: &str) -> &'a EnumInfo {
+		match &self.types[name] {
+			TypeInfo::Enum(info) => info,
+			_ => unreachable!("type should be an enum")
+		}
+	}
+
+	// enums are laid out like structs, with the i32 tag of the variant in the first slot and its payload after it, and so are closures
+	fn payload_offset(index: usize) -> i32 {
+		return (index as i32 + 1) * 8;
+	}
+
+	// structs are laid out like arrays, with an 8 byte slot for every field in declaration order
+	fn field_slot(&self, structure: &Node, field: &str) -> (i32, AbstractType) {
+		let info = match structure.data_type() {
+			AbstractType::Named(name) => self.struct_info(name),
+			_ => unreachable!("node should be a struct")
+		};
+
+		let index = info.fields.iter().position(|definition| definition.name == field).expect("struct should have the field");
+		return (index as i32 * 8, info.fields[index].data_type.clone());
+	}
+
+	fn element_type(array: &Node) -> &AbstractType {
+		match array.data_type() {
+			AbstractType::Array(element_type) => element_type,
+			_ => unreachable!("node should be an array")
+		}
 	}
 
 	fn build_node(&mut self, node: &Node) -> Value {
-		match node {
-			Node::Int(val) => {
-				self.builder.ins().iconst(I32, Imm64::new(*val as i64))
+		match &node.kind {
+			NodeKind::Int { value, int_type, .. } => {
+				self.builder.ins().iconst(integer_type(*int_type), Imm64::new(*value as i64))
 			},
-			Node::Add { lhs, rhs } => {
+			NodeKind::Float(value) => {
+				self.builder.ins().f64const(*value)
+			},
+			NodeKind::Add { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().iadd(lv, rv)
+				match lhs.data_type() {
+					AbstractType::Float => self.builder.ins().fadd(lv, rv),
+					_ => self.builder.ins().iadd(lv, rv)
+				}
 			},
-			Node::Subtract{ lhs, rhs } => {
+			NodeKind::Subtract{ lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().isub(lv, rv)
+				match lhs.data_type() {
+					AbstractType::Float => self.builder.ins().fsub(lv, rv),
+					_ => self.builder.ins().isub(lv, rv)
+				}
 			},
-			Node::Multiply { lhs, rhs } => {
+			NodeKind::Multiply { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().imul(lv, rv)
+				match lhs.data_type() {
+					AbstractType::Float => self.builder.ins().fmul(lv, rv),
+					_ => self.builder.ins().imul(lv, rv)
+				}
 			},
-			Node::Divide { lhs, rhs } => {
+			NodeKind::Divide { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().sdiv(lv, rv)
+				match lhs.data_type() {
+					AbstractType::Float => self.builder.ins().fdiv(lv, rv),
+					_ if lhs.integer_type().is_signed() => self.builder.ins().sdiv(lv, rv),
+					_ => self.builder.ins().udiv(lv, rv)
+				}
 			},
-			Node::Equal { lhs, rhs } => {
+			NodeKind::Remainder { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().icmp(IntCC::Equal, lv, rv)
+				match lhs.integer_type().is_signed() {
+					true => self.builder.ins().srem(lv, rv),
+					false => self.builder.ins().urem(lv, rv)
+				}
 			},
-			Node::NotEqual { lhs, rhs } => {
+			NodeKind::BitAnd { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().icmp(IntCC::NotEqual, lv, rv)
+				self.builder.ins().band(lv, rv)
 			},
-			Node::LessThanOrEqual { lhs, rhs } => {
+			NodeKind::BitOr { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().icmp(IntCC::SignedLessThanOrEqual, lv, rv)
+				self.builder.ins().bor(lv, rv)
 			},
-			Node::GreaterThanOrEqual { lhs, rhs } => {
+			NodeKind::BitXor { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
-				self.builder.ins().icmp(IntCC::SignedGreaterThanOrEqual, lv, rv)
+				self.builder.ins().bxor(lv, rv)
 			},
-			Node::LessThan { lhs, rhs } => {
+			NodeKind::ShiftLeft { lhs, rhs } => {
 				let lv = self.build_node(lhs);
-				let rv = self.build_node(rhs);
-				self.builder.ins().icmp(IntCC::SignedLessThan, lv, rv)
+				let rv = self.build_shift_amount(rhs);
+				self.builder.ins().ishl(lv, rv)
 			},
-			Node::GreaterThan { lhs, rhs } => {
+			NodeKind::ShiftRight { lhs, rhs } => {
 				let lv = self.build_node(lhs);
-				let rv = self.build_node(rhs);
-				self.builder.ins().icmp(IntCC::SignedGreaterThan, lv, rv)
+				let rv = self.build_shift_amount(rhs);
+				match lhs.integer_type().is_signed() {
+					true => self.builder.ins().sshr(lv, rv),
+					false => self.builder.ins().ushr(lv, rv)
+				}
+			},
+			NodeKind::Equal { lhs, rhs } => {
+				self.build_comparison(lhs, rhs, IntCC::Equal, IntCC::Equal, FloatCC::Equal)
+			},
+			NodeKind::NotEqual { lhs, rhs } => {
+				self.build_comparison(lhs, rhs, IntCC::NotEqual, IntCC::NotEqual, FloatCC::NotEqual)
+			},
+			NodeKind::LessThanOrEqual { lhs, rhs } => {
+				self.build_comparison(lhs, rhs, IntCC::SignedLessThanOrEqual, IntCC::UnsignedLessThanOrEqual, FloatCC::LessThanOrEqual)
+			},
+			NodeKind::GreaterThanOrEqual { lhs, rhs } => {
+				self.build_comparison(lhs, rhs, IntCC::SignedGreaterThanOrEqual, IntCC::UnsignedGreaterThanOrEqual, FloatCC::GreaterThanOrEqual)
+			},
+			NodeKind::LessThan { lhs, rhs } => {
+				self.build_comparison(lhs, rhs, IntCC::SignedLessThan, IntCC::UnsignedLessThan, FloatCC::LessThan)
+			},
+			NodeKind::GreaterThan { lhs, rhs } => {
+				self.build_comparison(lhs, rhs, IntCC::SignedGreaterThan, IntCC::UnsignedGreaterThan, FloatCC::GreaterThan)
+			},
+			NodeKind::Negate(operand) => {
+				let val = self.build_node(operand);
+				match operand.data_type() {
+					AbstractType::Float => self.builder.ins().fneg(val),
+					_ => self.builder.ins().ineg(val)
+				}
+			},
+			NodeKind::Cast { value, target } => {
+				let val = self.build_node(value);
+
+				let (from, to) = match (value.data_type(), target) {
+					(AbstractType::Float, AbstractType::Float) => return val,
+					(AbstractType::Float, AbstractType::Integer(to)) => return self.build_float_to_int(val, *to),
+					(AbstractType::Integer(from), AbstractType::Float) => return self.build_int_to_float(val, *from),
+					(AbstractType::Integer(from), AbstractType::Integer(to)) => (*from, *to),
+					_ => unreachable!("only numbers can be cast")
+				};
+
+				match from.bits().cmp(&to.bits()) {
+					std::cmp::Ordering::Greater => self.builder.ins().ireduce(integer_type(to), val),
+					std::cmp::Ordering::Less if from.is_signed() => self.builder.ins().sextend(integer_type(to), val),
+					std::cmp::Ordering::Less => self.builder.ins().uextend(integer_type(to), val),
+					std::cmp::Ordering::Equal => val
+				}
 			},
 
-			Node::Bool(val) => {
+			NodeKind::Bool(val) => {
 				self.builder.ins().bconst(B1, *val)
 			},
 	
-			Node::BoolEqual{ lhs, rhs } => {
+			NodeKind::BoolEqual{ lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
 				let li = self.builder.ins().bint(I8, lv);
 				let ri = self.builder.ins().bint(I8, rv);
 				self.builder.ins().icmp(IntCC::Equal, li, ri)
 			},
-			Node::BoolNotEqual { lhs, rhs } => {
+			NodeKind::BoolNotEqual { lhs, rhs } => {
 				let lv = self.build_node(lhs);
 				let rv = self.build_node(rhs);
 				let li = self.builder.ins().bint(I8, lv);
 				let ri = self.builder.ins().bint(I8, rv);
 				self.builder.ins().icmp(IntCC::NotEqual, li, ri)
 			},
+			NodeKind::Not(operand) => {
+				// bnot flips every bit of the register rather than just the low one
+				let val = self.build_node(operand);
+				let int = self.builder.ins().bint(I8, val);
+				self.builder.ins().icmp_imm(IntCC::Equal, int, 0)
+			},
+			NodeKind::And { lhs, rhs } => {
+				let lv = self.build_node(lhs);
+				let rhs_block = self.builder.create_block();
+				let after_block = self.builder.create_block();
+				self.builder.append_block_param(after_block, B1);
+
+				// a false lhs decides the result without evaluating rhs
+				self.builder.ins().brz(lv, after_block, &[lv]);
+				self.builder.ins().jump(rhs_block, &[]);
+
+				self.builder.switch_to_block(rhs_block);
+				let rv = self.build_node(rhs);
+				self.builder.ins().jump(after_block, &[rv]);
+
+				self.builder.switch_to_block(after_block);
+				self.builder.block_params(after_block)[0]
+			},
+			NodeKind::Or { lhs, rhs } => {
+				let lv = self.build_node(lhs);
+				let rhs_block = self.builder.create_block();
+				let after_block = self.builder.create_block();
+				self.builder.append_block_param(after_block, B1);
+
+				// a true lhs decides the result without evaluating rhs
+				self.builder.ins().brnz(lv, after_block, &[lv]);
+				self.builder.ins().jump(rhs_block, &[]);
+
+				self.builder.switch_to_block(rhs_block);
+				let rv = self.build_node(rhs);
+				self.builder.ins().jump(after_block, &[rv]);
+
+				self.builder.switch_to_block(after_block);
+				self.builder.block_params(after_block)[0]
+			},
 
-			Node::StringLiteral(val) => {
+			NodeKind::StringLiteral(val) => {
 				let mut data_context = DataContext::new();
 
 				// TODO: sigh comma groan clone bad
@@ -160,49 +549,295 @@ impl<'a, 'b, M: Module> BuildState<'a, 'b, M> {
 				self.builder.ins().symbol_value(pointer_type, global_value)
 			},
 
-			Node::Set { name, var_type, value } => {
-				let data_type = deabstract(var_type, &self.module);
-				let var = self.get_new_variable(String::from(name), data_type.expect("variable type cannot be void"));
-	
+			NodeKind::Interpolation(parts) => {
+				let mut strings = parts.iter().map(|part| self.build_formatted(part)).collect::<Vec<Value>>().into_iter();
+
+				let mut result = strings.next().expect("interpolated string should have parts");
+				for string in strings {
+					result = self.build_call("str_concat", &[result, string]).unwrap();
+				}
+
+				result
+			},
+
+			NodeKind::Set { name, var_type, value } => {
+				// built before declaring so that the value can refer to a variable it shadows
 				let value = self.build_node(value);
+
+				let data_type = deabstract(var_type.as_ref().expect("variable type should be inferred"), &self.module);
+				let var = self.get_new_variable(String::from(name), data_type.expect("variable type cannot be void"));
 				self.builder.def_var(var, value);
 				self.builder.use_var(var)
 			},
-			Node::Get { name } => {
-				let var = self.get_declared_variable(String::from(name)).expect("undeclared variable");
-				self.builder.use_var(var)
+			NodeKind::Get { name } => {
+				match self.get_declared_variable(String::from(name)) {
+					Some(var) => self.builder.use_var(var),
+					// the typecheck pass makes sure that anything else is a static or a function
+					None => match self.static_address(name) {
+						Some(address) => self.build_slot_load(node.data_type(), address, 0),
+						None => self.build_function_value(name, node.data_type())
+					}
+				}
+			},
+
+			NodeKind::Array(elements) => {
+				let values: Vec<Value> = elements.iter().map(|node| {
+					self.build_node(node)
+				}).collect();
+
+				let length = self.builder.ins().iconst(I32, values.len() as i64);
+				let array = self.build_call("zinc_array_new", &[length]).unwrap();
+				for (i, value) in values.into_iter().enumerate() {
+					let index = self.builder.ins().iconst(I32, i as i64);
+					let slot = self.build_element_slot(array, index);
+					self.build_slot_store(Self::element_type(node), slot, 0, value);
+				}
+
+				array
+			},
+			NodeKind::Index { array, index } => {
+				let array_value = self.build_node(array);
+				let index = self.build_node(index);
+				let slot = self.build_element_slot(array_value, index);
+				self.build_slot_load(Self::element_type(array), slot, 0)
 			},
 
-			Node::Function { name, args } => {
-				let args: Vec<Value> = args.iter().map(|node| {
+			NodeKind::Construct { name, fields } => {
+				let values: Vec<Value> = fields.iter().map(|(_, node)| {
 					self.build_node(node)
 				}).collect();
 
-				let imported_func: FuncRef = self.module.declare_func_in_func(
-					self.func_map[name],
-					self.builder.func
-				);
+				let field_count = self.struct_info(name).fields.len() as i64;
+				let field_count = self.builder.ins().iconst(I32, field_count);
+				let structure = self.build_call("zinc_struct_new", &[field_count]).unwrap();
+				for ((field, _), value) in fields.iter().zip(values) {
+					let (offset, field_type) = self.field_slot(node, field);
+					self.build_slot_store(&field_type, structure, offset, value);
+				}
 
-				let function_result = self.builder.ins().call(imported_func, args.as_slice());
-				match self.builder.inst_results(function_result).get(0) {
-					Some(val) => *val,
-					None => self.builder.ins().iconst(I32, 0) // TODO: make a better system
+				structure
+			},
+			NodeKind::Field { structure, field } => {
+				let pointer = self.build_node(structure);
+				let (offset, field_type) = self.field_slot(structure, field);
+				self.build_slot_load(&field_type, pointer, offset)
+			},
+
+			NodeKind::Variant { enum_name, variant, values } => {
+				let values: Vec<Value> = values.iter().map(|node| {
+					self.build_node(node)
+				}).collect();
+
+				let (tag, variant_info) = self.enum_info(enum_name).variant(variant).expect("enum should have the variant");
+				let slot_count = self.builder.ins().iconst(I32, variant_info.fields.len() as i64 + 1);
+				let pointer = self.build_call("zinc_struct_new", &[slot_count]).unwrap();
+				let tag = self.builder.ins().iconst(I32, tag as i64);
+				self.builder.ins().store(MemFlags::trusted(), tag, pointer, 0);
+				for (i, (field_type, value)) in variant_info.fields.iter().zip(values).enumerate() {
+					self.build_slot_store(field_type, pointer, Self::payload_offset(i), value);
 				}
+
+				pointer
+			},
+
+			NodeKind::Closure { name, captures, .. } => {
+				let values: Vec<(AbstractType, Value)> = captures.iter().map(|capture| {
+					let var = self.get_declared_variable(capture.name.clone()).expect("captured variable should be declared");
+					(capture.data_type.clone(), self.builder.use_var(var))
+				}).collect();
+
+				// the body is built separately, like any other function
+				self.build_closure(self.func_map[name], &values)
+			},
+
+			NodeKind::Function { .. } => self.build_function_node(node).expect("void result cannot be used as a value")
+		}
+	}
+
+	// calls a function, which returns None if it returns void
+	fn build_function_node(&mut self, node: &Node) -> Option<Value> {
+		let (name, args) = match &node.kind {
+			NodeKind::Function { name, args } => (name, args),
+			_ => unreachable!("node should be a function call")
+		};
+
+		let arg_values: Vec<Value> = args.iter().map(|node| {
+			self.build_node(node)
+		}).collect();
+
+		// variables and statics shadow functions, so either one with the name is a function pointer
+		let callee = match self.get_declared_variable(name.clone()) {
+			Some(var) => Some(self.builder.use_var(var)),
+			None => self.static_address(name).map(|address| {
+				let pointer_type = self.module.target_config().pointer_type();
+				self.builder.ins().load(pointer_type, MemFlags::trusted(), address, 0)
+			})
+		};
+
+		match (callee, name.as_str()) {
+			(Some(callee), _) => self.build_indirect_call(callee, args, &arg_values, node.data_type()),
+			(None, "len") => self.build_call("zinc_array_len", &arg_values),
+			(None, "push") => {
+				let slot = self.build_call("zinc_array_push", &arg_values[..1]).unwrap();
+				self.build_slot_store(Self::element_type(&args[0]), slot, 0, arg_values[1]);
+				None
+			},
+			(None, _) => self.build_call(name, &arg_values)
+		}
+	}
+
+	// builds a node whose value isn't used, which is the only place a void function can be called
+	fn build_discarded(&mut self, node: &Node) {
+		match node.kind {
+			NodeKind::Function { .. } => {
+				self.build_function_node(node);
+			},
+			_ => {
+				self.build_node(node);
+			}
+		}
+	}
+
+	// an unlabeled break or continue targets the innermost loop
+	fn get_loop(&mut self, label: Option<String>) -> &mut LoopBlocks {
+		self.loops.iter_mut().rev()
+			.find(|blocks| label.is_none() || blocks.label == label)
+			.expect("break or continue outside of a loop")
+	}
+
+	fn build_loop_body(&mut self, label: Option<String>, loop_statements: Vec<Statement>, continue_block: Block, after_block: Block) -> bool {
+		self.loops.push(LoopBlocks {
+			label,
+			continue_block,
+			after_block,
+			broken: false
+		});
+
+		self.build_block(loop_statements);
+		if !self.builder.is_filled() {
+			self.builder.ins().jump(continue_block, &[]);
+		}
+
+		return self.loops.pop().unwrap().broken;
+	}
+
+	// jumps to the block of the arm that matches the value, or traps if there is none
+	fn build_match_dispatch(&mut self, value: &Node, val: Value, arms: &[MatchArm], arm_blocks: &[Block]) {
+		let wildcard = arms.iter().position(|arm| matches!(arm.pattern, Pattern::Wildcard)).map(|i| arm_blocks[i]);
+
+		// structs can only be matched by a wildcard, which the literal comparisons below jump straight to
+		let enum_info = match value.data_type() {
+			AbstractType::Named(name) => match &self.types[name] {
+				TypeInfo::Enum(info) => Some(info),
+				TypeInfo::Struct(_) => None
+			},
+			_ => None
+		};
+
+		// enums switch on their tag, everything else compares the literals in order
+		if let Some(enum_info) = enum_info {
+			let default_block = wildcard.unwrap_or_else(|| self.builder.create_block());
+
+			let mut jump_table = JumpTableData::new();
+			for variant in enum_info.variants.iter() {
+				let arm = arms.iter().position(|arm| {
+					matches!(&arm.pattern, Pattern::Variant { variant: arm_variant, .. } if *arm_variant == variant.name)
+				});
+				jump_table.push_entry(arm.map_or(default_block, |i| arm_blocks[i]));
 			}
+			let jump_table = self.builder.create_jump_table(jump_table);
+
+			let tag = self.builder.ins().load(I32, MemFlags::trusted(), val, 0);
+			self.builder.ins().br_table(tag, default_block, jump_table);
+
+			if wildcard.is_none() {
+				self.builder.switch_to_block(default_block);
+				self.builder.ins().trap(TrapCode::UnreachableCodeReached);
+			}
+			return;
+		}
+
+		for (arm, block) in arms.iter().zip(arm_blocks) {
+			let literal = match &arm.pattern {
+				Pattern::Literal(literal) => literal,
+				_ => {
+					self.builder.ins().jump(*block, &[]);
+					return;
+				}
+			};
+
+			match &literal.kind {
+				NodeKind::Bool(true) => self.builder.ins().brnz(val, *block, &[]),
+				NodeKind::Bool(false) => self.builder.ins().brz(val, *block, &[]),
+				_ => {
+					let literal_val = self.build_node(literal);
+					let equal = match literal.data_type() {
+						AbstractType::String => self.build_call("str_eq", &[val, literal_val]).unwrap(),
+						_ => self.builder.ins().icmp(IntCC::Equal, val, literal_val)
+					};
+					self.builder.ins().brnz(equal, *block, &[])
+				}
+			};
+
+			let next_block = self.builder.create_block();
+			self.builder.ins().jump(next_block, &[]);
+			self.builder.switch_to_block(next_block);
 		}
+
+		// only reachable for a bool that matched neither true nor false
+		self.builder.ins().trap(TrapCode::UnreachableCodeReached);
 	}
 
 	pub fn build_statements(&mut self, statements: Vec<Statement>) {
 		for statement in statements {
-			match statement {
-				Statement::Node(node) => {
-					self.build_node(&node);
+			// anything after a return is unreachable, and cranelift refuses to add to a filled block
+			if self.builder.is_filled() {
+				break;
+			}
+
+			match statement.kind {
+				StatementKind::Node(node) => {
+					self.build_discarded(&node);
+				},
+				StatementKind::Assign { name, value } => {
+					let data_type = value.data_type().clone();
+					let value = self.build_node(&value);
+
+					match self.get_declared_variable(name.clone()) {
+						Some(var) => self.builder.def_var(var, value),
+						None => {
+							let address = self.static_address(&name).expect("undeclared variable");
+							self.build_slot_store(&data_type, address, 0, value);
+						}
+					}
 				},
-				Statement::Return(node) => {
+				StatementKind::AssignIndex { array, index, value } => {
+					// the slot is looked up after building the value, which could push to the array and move its elements
+					let array_value = self.build_node(&array);
+					let index = self.build_node(&index);
+					let value = self.build_node(&value);
+					let slot = self.build_element_slot(array_value, index);
+					self.build_slot_store(Self::element_type(&array), slot, 0, value);
+				},
+				StatementKind::AssignField { structure, field, value } => {
+					let pointer = self.build_node(&structure);
+					let value = self.build_node(&value);
+					let (offset, field_type) = self.field_slot(&structure, &field);
+					self.build_slot_store(&field_type, pointer, offset, value);
+				},
+				// a void function can return the result of another void function, which has no value
+				StatementKind::Return(Some(node)) if *node.data_type() == AbstractType::Void => {
+					self.build_discarded(&node);
+					self.builder.ins().return_(&[]);
+				},
+				StatementKind::Return(Some(node)) => {
 					let val = self.build_node(&node);
 					self.builder.ins().return_(&[val]);
 				},
-				Statement::If { condition, branch, else_branch } => {
+				StatementKind::Return(None) => {
+					self.builder.ins().return_(&[]);
+				},
+				StatementKind::If { condition, branch, else_branch } => {
 					let val = self.build_node(&condition);
 
 					let cond_block = self.builder.create_block();
@@ -212,7 +847,7 @@ impl<'a, 'b, M: Module> BuildState<'a, 'b, M> {
 					self.builder.ins().jump(else_block, &[]);
 
 					self.builder.switch_to_block(else_block);
-					self.build_statements(else_branch);
+					self.build_block(else_branch);
 
 					let mut after_block = None;
 					if !self.builder.is_filled() {
@@ -222,7 +857,7 @@ impl<'a, 'b, M: Module> BuildState<'a, 'b, M> {
 					}
 
 					self.builder.switch_to_block(cond_block);
-					self.build_statements(branch);
+					self.build_block(branch);
 					if !self.builder.is_filled() {
 						match after_block {
 							Some(after_block) => {
@@ -245,7 +880,7 @@ impl<'a, 'b, M: Module> BuildState<'a, 'b, M> {
 						}
 					}
 				},
-				Statement::While { condition, loop_statements } => {
+				StatementKind::While { label, condition, loop_statements } => {
 					let test_block = self.builder.create_block();
 					self.builder.ins().jump(test_block, &[]);
 
@@ -258,18 +893,116 @@ impl<'a, 'b, M: Module> BuildState<'a, 'b, M> {
 					self.builder.ins().jump(after_block, &[]);
 
 					self.builder.switch_to_block(loop_block);
-					self.build_statements(loop_statements);
-					self.builder.ins().jump(test_block, &[]);
+					self.build_loop_body(label, loop_statements, test_block, after_block);
 
 					self.builder.switch_to_block(after_block);
 				},
-				Statement::InfiniteLoop(loop_statements) => {
+				StatementKind::InfiniteLoop { label, loop_statements } => {
 					let loop_block = self.builder.create_block();
+					let after_block = self.builder.create_block();
 					self.builder.ins().jump(loop_block, &[]);
 					self.builder.switch_to_block(loop_block);
 
-					self.build_statements(loop_statements);
-					self.builder.ins().jump(loop_block, &[]);
+					if self.build_loop_body(label, loop_statements, loop_block, after_block) {
+						self.builder.switch_to_block(after_block);
+					}
+				},
+				StatementKind::For { label, variable, start, end, inclusive, step, loop_statements } => {
+					let int_type = start.integer_type();
+					let var_type = integer_type(int_type);
+					let (less_than, less_than_or_equal, greater_than) = match int_type.is_signed() {
+						true => (IntCC::SignedLessThan, IntCC::SignedLessThanOrEqual, IntCC::SignedGreaterThan),
+						false => (IntCC::UnsignedLessThan, IntCC::UnsignedLessThanOrEqual, IntCC::UnsignedGreaterThan)
+					};
+
+					let start = self.build_node(&start);
+					let end = self.build_node(&end);
+					let counter = self.get_hidden_variable(var_type);
+					self.builder.def_var(counter, start);
+
+					let test_block = self.builder.create_block();
+					let loop_block = self.builder.create_block();
+					let step_block = self.builder.create_block();
+					let after_block = self.builder.create_block();
+					self.builder.ins().jump(test_block, &[]);
+
+					self.builder.switch_to_block(test_block);
+					let i = self.builder.use_var(counter);
+					let in_range = self.builder.ins().icmp(match inclusive {
+						true => less_than_or_equal,
+						false => less_than
+					}, i, end);
+					self.builder.ins().brnz(in_range, loop_block, &[]);
+					self.builder.ins().jump(after_block, &[]);
+
+					self.builder.switch_to_block(loop_block);
+					self.scopes.push(std::collections::HashMap::new());
+					let var = self.get_new_variable(variable, var_type);
+					self.builder.def_var(var, i);
+					self.build_loop_body(label, loop_statements, step_block, after_block);
+					self.scopes.pop();
+
+					self.builder.switch_to_block(step_block);
+					if step as i128 > int_type.max() {
+						// a step this large always leaves the range after the first iteration
+						self.builder.ins().jump(after_block, &[]);
+					} else {
+						// the step is positive, so a next value that isn't greater has wrapped past the largest value of the type
+						let i = self.builder.use_var(counter);
+						let next = self.builder.ins().iadd_imm(i, step as i64);
+						self.builder.def_var(counter, next);
+						let no_overflow = self.builder.ins().icmp(greater_than, next, i);
+						self.builder.ins().brz(no_overflow, after_block, &[]);
+						self.builder.ins().jump(test_block, &[]);
+					}
+
+					self.builder.switch_to_block(after_block);
+				},
+				StatementKind::Match { value, arms } => {
+					let val = self.build_node(&value);
+					let arm_blocks: Vec<Block> = arms.iter().map(|_| self.builder.create_block()).collect();
+					self.build_match_dispatch(&value, val, &arms, &arm_blocks);
+
+					let mut after_block = None;
+					for (arm, block) in arms.into_iter().zip(arm_blocks) {
+						self.builder.switch_to_block(block);
+						self.scopes.push(std::collections::HashMap::new());
+
+						if let Pattern::Variant { enum_name, variant, bindings } = arm.pattern {
+							let (_, variant_info) = self.enum_info(&enum_name).variant(&variant).expect("enum should have the variant");
+							for (i, (binding, field_type)) in bindings.into_iter().zip(variant_info.fields.iter()).enumerate() {
+								if let Some(name) = binding {
+									let payload = self.build_slot_load(field_type, val, Self::payload_offset(i));
+									let data_type = deabstract(field_type, &self.module).expect("payloads cannot be void");
+									let var = self.get_new_variable(name, data_type);
+									self.builder.def_var(var, payload);
+								}
+							}
+						}
+
+						self.build_block(arm.statements);
+						self.scopes.pop();
+
+						if !self.builder.is_filled() {
+							let after = *after_block.get_or_insert_with(|| self.builder.create_block());
+							self.builder.ins().jump(after, &[]);
+						}
+					}
+
+					// every arm returned or jumped out of a loop otherwise
+					if let Some(after_block) = after_block {
+						self.builder.switch_to_block(after_block);
+					}
+				},
+				StatementKind::Break(label) => {
+					let blocks = self.get_loop(label);
+					blocks.broken = true;
+					let after_block = blocks.after_block;
+					self.builder.ins().jump(after_block, &[]);
+				},
+				StatementKind::Continue(label) => {
+					let continue_block = self.get_loop(label).continue_block;
+					self.builder.ins().jump(continue_block, &[]);
 				}
 			};
 		}
@@ -0,0 +1,142 @@
+use crate::node::{AbstractType, IntegerType, Node, NodeKind, Span};
+use crate::typecheck::TypeErrorKind;
+
+/// The value of a `const`, which the typecheck pass puts in place of every use of it.
+#[derive(Debug, Clone)]
+pub enum Constant {
+	Int(i128, IntegerType),
+	Float(f64),
+	Bool(bool),
+	String(String)
+}
+
+impl Constant {
+	pub fn to_node_kind(&self) -> NodeKind {
+		match self {
+			Constant::Int(value, int_type) => NodeKind::Int {
+				value: *value,
+				int_type: *int_type,
+				suffixed: true
+			},
+			Constant::Float(value) => NodeKind::Float(*value),
+			Constant::Bool(value) => NodeKind::Bool(*value),
+			Constant::String(value) => NodeKind::StringLiteral(value.clone())
+		}
+	}
+}
+
+type Evaluated = Result<Constant, (TypeErrorKind, Span)>;
+
+// the operators behave like they do in the interpreter, except that their errors are found before the program runs
+fn checked(value: Option<i128>, int_type: IntegerType, message: &'static str, span: Span) -> Evaluated {
+	match value {
+		Some(value) if value >= int_type.min() && value <= int_type.max() => Ok(Constant::Int(value, int_type)),
+		_ => Err((TypeErrorKind::InvalidConstant(message), span))
+	}
+}
+
+// keeps the low bits of the value, like bitwise operators and casts do
+fn wrapping(value: i128, int_type: IntegerType) -> Constant {
+	let modulus = 1i128 << int_type.bits();
+	let value = value.rem_euclid(modulus);
+
+	match value > int_type.max() {
+		true => Constant::Int(value - modulus, int_type),
+		false => Constant::Int(value, int_type)
+	}
+}
+
+fn arithmetic(lhs: &Node, rhs: &Node, span: Span, message: &'static str, int_op: fn(i128, i128) -> Option<i128>, float_op: fn(f64, f64) -> f64) -> Evaluated {
+	match (evaluate(lhs)?, evaluate(rhs)?) {
+		(Constant::Int(l, int_type), Constant::Int(r, _)) => checked(int_op(l, r), int_type, message, span),
+		(Constant::Float(l), Constant::Float(r)) => Ok(Constant::Float(float_op(l, r))),
+		_ => unreachable!("operands should be numbers of the same type")
+	}
+}
+
+fn integral(lhs: &Node, rhs: &Node, op: impl Fn(i128, i128, IntegerType) -> Evaluated) -> Evaluated {
+	match (evaluate(lhs)?, evaluate(rhs)?) {
+		(Constant::Int(l, int_type), Constant::Int(r, _)) => op(l, r, int_type),
+		_ => unreachable!("operands should be integers of the same type")
+	}
+}
+
+fn comparison(lhs: &Node, rhs: &Node, int_op: fn(&i128, &i128) -> bool, float_op: fn(&f64, &f64) -> bool) -> Evaluated {
+	match (evaluate(lhs)?, evaluate(rhs)?) {
+		(Constant::Int(l, _), Constant::Int(r, _)) => Ok(Constant::Bool(int_op(&l, &r))),
+		(Constant::Float(l), Constant::Float(r)) => Ok(Constant::Bool(float_op(&l, &r))),
+		_ => unreachable!("operands should be numbers of the same type")
+	}
+}
+
+fn boolean(node: &Node) -> Result<bool, (TypeErrorKind, Span)> {
+	match evaluate(node)? {
+		Constant::Bool(value) => Ok(value),
+		_ => unreachable!("operand should be a bool")
+	}
+}
+
+fn shift(amount: i128, int_type: IntegerType, span: Span) -> Result<i128, (TypeErrorKind, Span)> {
+	match amount >= 0 && amount < int_type.bits() as i128 {
+		true => Ok(amount),
+		false => Err((TypeErrorKind::InvalidConstant("attempt to shift by a negative amount or by more bits than the type has"), span))
+	}
+}
+
+/// Works out the value of a typechecked node, whose consts have already been replaced by their values.
+pub fn evaluate(node: &Node) -> Evaluated {
+	let span = node.span;
+
+	match &node.kind {
+		NodeKind::Int { value, int_type, .. } => Ok(Constant::Int(*value, *int_type)),
+		NodeKind::Float(value) => Ok(Constant::Float(*value)),
+		NodeKind::Bool(value) => Ok(Constant::Bool(*value)),
+		NodeKind::StringLiteral(value) => Ok(Constant::String(value.clone())),
+
+		NodeKind::Add { lhs, rhs } => arithmetic(lhs, rhs, span, "attempt to add with overflow", i128::checked_add, |l, r| l + r),
+		NodeKind::Subtract { lhs, rhs } => arithmetic(lhs, rhs, span, "attempt to subtract with overflow", i128::checked_sub, |l, r| l - r),
+		NodeKind::Multiply { lhs, rhs } => arithmetic(lhs, rhs, span, "attempt to multiply with overflow", i128::checked_mul, |l, r| l * r),
+		NodeKind::Divide { lhs, rhs } => arithmetic(lhs, rhs, span, "attempt to divide by zero or with overflow", i128::checked_div, |l, r| l / r),
+		NodeKind::Remainder { lhs, rhs } => integral(lhs, rhs, |l, r, int_type| {
+			// the remainder is only defined when the quotient is
+			checked(l.checked_div(r), int_type, "attempt to calculate the remainder with a divisor of zero or with overflow", span)?;
+			Ok(wrapping(l % r, int_type))
+		}),
+		NodeKind::BitAnd { lhs, rhs } => integral(lhs, rhs, |l, r, int_type| Ok(wrapping(l & r, int_type))),
+		NodeKind::BitOr { lhs, rhs } => integral(lhs, rhs, |l, r, int_type| Ok(wrapping(l | r, int_type))),
+		NodeKind::BitXor { lhs, rhs } => integral(lhs, rhs, |l, r, int_type| Ok(wrapping(l ^ r, int_type))),
+		NodeKind::ShiftLeft { lhs, rhs } => integral(lhs, rhs, |l, r, int_type| Ok(wrapping(l << shift(r, int_type, span)?, int_type))),
+		// unsigned values are never negative once widened, so this is a logical shift for them
+		NodeKind::ShiftRight { lhs, rhs } => integral(lhs, rhs, |l, r, int_type| Ok(wrapping(l >> shift(r, int_type, span)?, int_type))),
+
+		NodeKind::Equal { lhs, rhs } => comparison(lhs, rhs, i128::eq, f64::eq),
+		NodeKind::NotEqual { lhs, rhs } => comparison(lhs, rhs, i128::ne, f64::ne),
+		NodeKind::LessThanOrEqual { lhs, rhs } => comparison(lhs, rhs, i128::le, f64::le),
+		NodeKind::GreaterThanOrEqual { lhs, rhs } => comparison(lhs, rhs, i128::ge, f64::ge),
+		NodeKind::LessThan { lhs, rhs } => comparison(lhs, rhs, i128::lt, f64::lt),
+		NodeKind::GreaterThan { lhs, rhs } => comparison(lhs, rhs, i128::gt, f64::gt),
+
+		NodeKind::Negate(operand) => match evaluate(operand)? {
+			Constant::Int(value, int_type) => checked(Some(-value), int_type, "attempt to negate with overflow", span),
+			Constant::Float(value) => Ok(Constant::Float(-value)),
+			_ => unreachable!("operand should be a number")
+		},
+		NodeKind::Cast { value, target } => match (evaluate(value)?, target) {
+			(Constant::Float(value), AbstractType::Float) => Ok(Constant::Float(value)),
+			// saturates and turns NaN into 0
+			(Constant::Float(value), AbstractType::Integer(int_type)) => Ok(Constant::Int((value as i128).clamp(int_type.min(), int_type.max()), *int_type)),
+			(Constant::Int(value, _), AbstractType::Float) => Ok(Constant::Float(value as f64)),
+			(Constant::Int(value, _), AbstractType::Integer(int_type)) => Ok(wrapping(value, *int_type)),
+			_ => unreachable!("casts should be between numbers")
+		},
+
+		NodeKind::BoolEqual { lhs, rhs } => Ok(Constant::Bool(boolean(lhs)? == boolean(rhs)?)),
+		NodeKind::BoolNotEqual { lhs, rhs } => Ok(Constant::Bool(boolean(lhs)? != boolean(rhs)?)),
+		NodeKind::Not(operand) => Ok(Constant::Bool(!boolean(operand)?)),
+		// the right hand side is only evaluated when it's needed, like at runtime
+		NodeKind::And { lhs, rhs } => Ok(Constant::Bool(boolean(lhs)? && boolean(rhs)?)),
+		NodeKind::Or { lhs, rhs } => Ok(Constant::Bool(boolean(lhs)? || boolean(rhs)?)),
+
+		_ => Err((TypeErrorKind::NotConstant, span))
+	}
+}
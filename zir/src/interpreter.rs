@@ -1,8 +1,12 @@
-use crate::node::{FileDescription, Statement, Node, AbstractType, FunctionInfo};
+use crate::node::{FileDescription, Statement, StatementKind, Node, NodeKind, AbstractType, IntegerType, FunctionInfo, Pattern, MatchArm, Span};
+use crate::typecheck::{typecheck, TypeError};
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::cell::RefCell;
 
 #[derive(Debug)]
 pub enum RuntimeResult {
+	InvalidProgram(Vec<TypeError>),
 	TypeError(&'static str),
 	IncorrectArgumentNumber,
 	UndefinedVariable(String),
@@ -11,10 +15,71 @@ pub enum RuntimeResult {
 	SubtractionOverflow,
 	MultiplicationOverflow,
 	DivisionError,
+	RemainderError,
+	ShiftOverflow,
+	NegationOverflow,
+	IndexOutOfBounds {
+		index: i32,
+		length: usize
+	},
 	Panic
 }
 
-pub fn interpret(file_description: FileDescription) -> Result<String, RuntimeResult> {
+impl std::fmt::Display for RuntimeResult {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			RuntimeResult::InvalidProgram(errors) => write!(f, "program failed to typecheck with {} error(s)", errors.len()),
+			RuntimeResult::TypeError(message) => write!(f, "type error: {}", message),
+			RuntimeResult::IncorrectArgumentNumber => write!(f, "function called with incorrect number of arguments"),
+			RuntimeResult::UndefinedVariable(name) => write!(f, "undeclared variable `{}`", name),
+			RuntimeResult::UndefinedFunction(name) => write!(f, "undefined function `{}`", name),
+			RuntimeResult::AdditionOverflow => write!(f, "attempt to add with overflow"),
+			RuntimeResult::SubtractionOverflow => write!(f, "attempt to subtract with overflow"),
+			RuntimeResult::MultiplicationOverflow => write!(f, "attempt to multiply with overflow"),
+			RuntimeResult::DivisionError => write!(f, "attempt to divide by zero or with overflow"),
+			RuntimeResult::RemainderError => write!(f, "attempt to calculate the remainder with a divisor of zero or with overflow"),
+			RuntimeResult::ShiftOverflow => write!(f, "attempt to shift by a negative amount or by more bits than the type has"),
+			RuntimeResult::NegationOverflow => write!(f, "attempt to negate with overflow"),
+			RuntimeResult::IndexOutOfBounds { index, length } => write!(f, "index out of bounds: the length is {} but the index is {}", length, index),
+			RuntimeResult::Panic => write!(f, "explicit panic")
+		}
+	}
+}
+
+/// A runtime failure along with the innermost node that caused it, if any.
+#[derive(Debug)]
+pub struct RuntimeError {
+	pub result: RuntimeResult,
+	pub span: Option<Span>
+}
+
+impl RuntimeError {
+	fn or_at(self, span: Span) -> Self {
+		RuntimeError {
+			result: self.result,
+			span: self.span.or(Some(span))
+		}
+	}
+}
+
+impl From<RuntimeResult> for RuntimeError {
+	fn from(result: RuntimeResult) -> Self {
+		RuntimeError {
+			result,
+			span: None
+		}
+	}
+}
+
+impl std::fmt::Display for RuntimeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.result)
+	}
+}
+
+pub fn interpret(mut file_description: FileDescription) -> Result<String, RuntimeError> {
+	typecheck(&mut file_description).map_err(RuntimeResult::InvalidProgram)?;
+
 	let interpreter_state = InterpreterState {
 		file_description
 	};
@@ -22,45 +87,124 @@ pub fn interpret(file_description: FileDescription) -> Result<String, RuntimeRes
 	Ok(interpreter_state.run_main()?)
 }
 
+pub type SharedArray = Rc<RefCell<Vec<Value>>>;
+pub type SharedStruct = Rc<RefCell<HashMap<String, Value>>>;
+
 #[derive(Debug, Clone)]
 pub enum Value {
-	Integer(i32),
+	I8(i8),
+	I16(i16),
+	I32(i32),
+	I64(i64),
+	U8(u8),
+	U16(u16),
+	U32(u32),
+	U64(u64),
+	Float(f64),
 	Boolean(bool),
 	String(String),
+	// arrays and structs are shared rather than copied, like they are in compiled code
+	Array(AbstractType, SharedArray),
+	Struct(String, SharedStruct),
+	// the enum, the variant and its payload, which can't be modified so it doesn't need to be shared
+	Enum(String, String, Vec<Value>),
+	// the name of a user-defined, standard library or lifted closure function, the values a closure captured, and its type
+	Function(String, Vec<Value>, AbstractType),
+	// the result of a void function, which can't be stored anywhere
 	None
 }
 
 impl Value {
+	/// Widens an integer of any type, so that arithmetic on two of them can't overflow before it is range checked.
+	pub fn to_integer(&self) -> Option<(i128, IntegerType)> {
+		match self {
+			Value::I8(val) => Some((*val as i128, IntegerType::I8)),
+			Value::I16(val) => Some((*val as i128, IntegerType::I16)),
+			Value::I32(val) => Some((*val as i128, IntegerType::I32)),
+			Value::I64(val) => Some((*val as i128, IntegerType::I64)),
+			Value::U8(val) => Some((*val as i128, IntegerType::U8)),
+			Value::U16(val) => Some((*val as i128, IntegerType::U16)),
+			Value::U32(val) => Some((*val as i128, IntegerType::U32)),
+			Value::U64(val) => Some((*val as i128, IntegerType::U64)),
+			_ => None
+		}
+	}
+
+	/// Returns None if the value doesn't fit in the integer type.
+	pub fn from_integer(value: i128, int_type: IntegerType) -> Option<Value> {
+		match value >= int_type.min() && value <= int_type.max() {
+			true => Some(Value::wrapping_from_integer(value, int_type)),
+			false => None
+		}
+	}
+
+	/// Truncates the value to the integer type, the same way `as` does.
+	pub fn wrapping_from_integer(value: i128, int_type: IntegerType) -> Value {
+		match int_type {
+			IntegerType::I8 => Value::I8(value as i8),
+			IntegerType::I16 => Value::I16(value as i16),
+			IntegerType::I32 => Value::I32(value as i32),
+			IntegerType::I64 => Value::I64(value as i64),
+			IntegerType::U8 => Value::U8(value as u8),
+			IntegerType::U16 => Value::U16(value as u16),
+			IntegerType::U32 => Value::U32(value as u32),
+			IntegerType::U64 => Value::U64(value as u64)
+		}
+	}
+
 	pub fn to_abstract(&self) -> AbstractType {
 		match self {
-			Value::Integer(_) => AbstractType::Integer,
+			Value::I8(_) | Value::I16(_) | Value::I32(_) | Value::I64(_) |
+			Value::U8(_) | Value::U16(_) | Value::U32(_) | Value::U64(_) => {
+				AbstractType::Integer(self.to_integer().unwrap().1)
+			},
+			Value::Float(_) => AbstractType::Float,
 			Value::Boolean(_) => AbstractType::Boolean,
 			Value::String(_) => AbstractType::String,
+			Value::Array(element_type, _) => AbstractType::Array(Box::new(element_type.clone())),
+			Value::Struct(name, _) | Value::Enum(name, _, _) => AbstractType::Named(name.clone()),
+			Value::Function(_, _, function_type) => function_type.clone(),
 			Value::None => AbstractType::Void
 		}
 	}
 }
 
+// the operands of an operator that accepts both integers and floats
+enum Numbers {
+	Integers(i128, i128, IntegerType),
+	Floats(f64, f64)
+}
+
 struct InterpreterState {
 	file_description: FileDescription
 }
 
 impl InterpreterState {
-	fn run_main(self) -> Result<String, RuntimeResult> {
+	fn run_main(self) -> Result<String, RuntimeError> {
 		let mut output_string = String::new();
+		let mut globals = HashMap::new();
 
 		let mut main_function = FunctionState {
 			info: &FunctionInfo {
 				body: self.file_description.statements,
 				args: vec![],
-				return_type: AbstractType::Void
+				return_type: AbstractType::Void,
+				span: Span::default(),
+				captures: None
 			},
-			variables: HashMap::new(),
+			scopes: vec![HashMap::new()],
 			functions: &self.file_description.functions,
+			globals: &mut globals,
 			output_string: &mut output_string
 		};
 
-		main_function.run(vec![])?;
+		// statics are set at the start of the program, in the order they are declared
+		for global in self.file_description.globals.iter() {
+			let value = main_function.eval_node(&global.value).map_err(|err| err.or_at(global.span))?;
+			main_function.globals.insert(global.name.clone(), value);
+		}
+
+		main_function.run(vec![], vec![])?;
 		Ok(output_string)
 	}
 }
@@ -103,7 +247,7 @@ fn test_interpreter() {
 
 		let n: i32 = 50;
 		while (n > 0) {
-			let n: i32 = n - 1;
+			n = n - 1;
 		}
 		assert_int_eq(n, 0);
 
@@ -123,42 +267,113 @@ fn test_interpreter() {
 		assert_str_eq(str_concat("br", "uh"), "bruh");
 
 		print_int(60 + 9);
+		print_float(0.1 + 0.2);
+		print_float(-2.5e-7);
+		print_float(1234567.0);
+		print_float(1.0 / 0.0);
 	"#).expect("failed to parse");
 
-	assert_eq!(interpret(file_description).expect("no runtime failures"), "69\n");
+	assert_eq!(interpret(file_description).expect("no runtime failures"), "69\n0.30000000000000004\n-2.5e-07\n1234567\ninf\n");
+}
+
+#[test]
+fn test_runtime_errors() {
+	let run = |code: &str| interpret(crate::parse::parse(code).expect("failed to parse")).unwrap_err();
+
+	let error = run("let x: i32 = -2147483648; print_int(-x);");
+	assert!(matches!(error.result, RuntimeResult::NegationOverflow));
+	assert_eq!(error.span, Some(crate::node::Span { start: 36, end: 38 }));
+
+	let error = run("print_int(2147483647 + 1);");
+	assert!(matches!(error.result, RuntimeResult::AdditionOverflow));
+
+	let error = run("print_int(1 / (1 - 1));");
+	assert!(matches!(error.result, RuntimeResult::DivisionError));
+
+	let error = run("print_int(7 % 0);");
+	assert!(matches!(error.result, RuntimeResult::RemainderError));
+
+	let error = run("print_int(1 << 32);");
+	assert!(matches!(error.result, RuntimeResult::ShiftOverflow));
+
+	let error = run("print_int(1 >> -1);");
+	assert!(matches!(error.result, RuntimeResult::ShiftOverflow));
+
+	let error = run("print_u8(255u8 + 1u8);");
+	assert!(matches!(error.result, RuntimeResult::AdditionOverflow));
+
+	let error = run("print_u32(0u32 - 1u32);");
+	assert!(matches!(error.result, RuntimeResult::SubtractionOverflow));
+
+	let error = run("print_i64(-9223372036854775808i64 / -1i64);");
+	assert!(matches!(error.result, RuntimeResult::DivisionError));
+
+	let error = run("print_i8(1i8 << 8i8);");
+	assert!(matches!(error.result, RuntimeResult::ShiftOverflow));
+
+	let error = run("let n: i64 = 1 as i64; let m: i64 = n << (70 as i64);");
+	assert_eq!(error.to_string(), "attempt to shift by a negative amount or by more bits than the type has");
+
+	let error = run("let a: [i32] = [1, 2]; print_int(a[2]);");
+	assert_eq!(error.to_string(), "index out of bounds: the length is 2 but the index is 2");
+	assert_eq!(error.span, Some(crate::node::Span { start: 33, end: 37 }));
+
+	let error = run("let a: [bool] = []; push(a, true); a[-1] = false;");
+	assert!(matches!(error.result, RuntimeResult::IndexOutOfBounds { index: -1, length: 1 }));
+}
+
+// signals that unwind statements until a loop or the function body handles them
+enum ControlFlow {
+	Return(Value),
+	Break(Option<String>),
+	Continue(Option<String>)
+}
+
+// an unlabeled break or continue targets the innermost loop
+fn targets_loop(label: &Option<String>, target: &Option<String>) -> bool {
+	return target.is_none() || target == label;
 }
 
 struct FunctionState<'a> {
 	info: &'a FunctionInfo,
-	variables: HashMap<String, Value>,
+	// innermost scope last
+	scopes: Vec<HashMap<String, Value>>,
 	functions: &'a HashMap<String, FunctionInfo>,
+	// the statics, which every function shares
+	globals: &'a mut HashMap<String, Value>,
 	output_string: &'a mut String
 }
 
 impl<'a> FunctionState<'a> {
-	fn run(&mut self, arguments: Vec<Value>) -> Result<Value, RuntimeResult> {
+	fn run(&mut self, captured: Vec<Value>, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
 		if arguments.len() != self.info.args.len() {
-			return Err(RuntimeResult::IncorrectArgumentNumber)
+			return Err(RuntimeResult::IncorrectArgumentNumber.into())
+		}
+
+		// arguments are declared afterwards, so that they shadow the captured variables like they do in the typecheck pass
+		for (value, capture) in captured.into_iter().zip(self.info.captures.iter().flatten()) {
+			self.scopes.last_mut().unwrap().insert(capture.name.clone(), value);
 		}
 
 		for (arg, def) in arguments.iter().zip(self.info.args.iter()) {
 			if arg.to_abstract() != def.data_type {
-				return Err(RuntimeResult::TypeError("user-defined function called with incorrect arguments"))
+				return Err(RuntimeResult::TypeError("user-defined function called with incorrect arguments").into())
 			}
-			self.variables.insert(def.name.clone(), arg.clone());
+			self.scopes.last_mut().unwrap().insert(def.name.clone(), arg.clone());
 		}
 
 		match self.eval_statements(&self.info.body)? {
-			Some(val) => Ok(val),
+			Some(ControlFlow::Return(val)) => Ok(val),
+			Some(ControlFlow::Break(_) | ControlFlow::Continue(_)) => unreachable!("typecheck rejects break and continue outside of a loop"),
 			None => Ok(Value::None)
 		}
 	}
 
-	fn eval_statements(&mut self, statements: &Vec<Statement>) -> Result<Option<Value>, RuntimeResult> {
+	fn eval_statements(&mut self, statements: &Vec<Statement>) -> Result<Option<ControlFlow>, RuntimeError> {
 		for statement in statements {
 			match self.eval_statement(statement)? {
-				Some(returned_val) => {
-					return Ok(Some(returned_val));
+				Some(flow) => {
+					return Ok(Some(flow));
 				}
 				_ => ()
 			}
@@ -167,32 +382,84 @@ impl<'a> FunctionState<'a> {
 		return Ok(None);
 	}
 
-	fn eval_statement(&mut self, statement: &Statement) -> Result<Option<Value>, RuntimeResult> {
+	fn eval_block(&mut self, statements: &Vec<Statement>) -> Result<Option<ControlFlow>, RuntimeError> {
+		self.scopes.push(HashMap::new());
+		let result = self.eval_statements(statements);
+		self.scopes.pop();
+		return result;
+	}
+
+	// variables shadow statics
+	fn lookup(&mut self, name: &str) -> Option<&mut Value> {
+		match self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) {
+			Some(value) => Some(value),
+			None => self.globals.get_mut(name)
+		}
+	}
+
+	fn eval_statement(&mut self, statement: &Statement) -> Result<Option<ControlFlow>, RuntimeError> {
+		self.eval_statement_kind(&statement.kind).map_err(|err| err.or_at(statement.span))
+	}
+
+	fn eval_statement_kind(&mut self, statement: &StatementKind) -> Result<Option<ControlFlow>, RuntimeError> {
 		match statement {
-			Statement::Node(node) => {
+			StatementKind::Node(node) => {
 				self.eval_node(node)?;
 				Ok(None)
 			},
-			Statement::Return(node) => {
-				Ok(Some(self.eval_node(node)?))
+			StatementKind::Assign { name, value } => {
+				let value = self.eval_node(value)?;
+
+				match self.lookup(name) {
+					Some(variable) => {
+						if variable.to_abstract() != value.to_abstract() {
+							return Err(RuntimeResult::TypeError("value must be same type as variable is declared").into());
+						}
+
+						*variable = value;
+						Ok(None)
+					},
+					None => Err(RuntimeResult::UndefinedVariable(name.to_string()).into())
+				}
+			},
+			StatementKind::AssignIndex { array, index, value } => {
+				let (elements, index) = self.eval_array_index(array, index)?;
+				let value = self.eval_node(value)?;
+
+				let index = element_index(&elements.borrow(), index)?;
+				elements.borrow_mut()[index] = value;
+				Ok(None)
 			},
-			Statement::If { condition, branch, else_branch } => {
+			StatementKind::AssignField { structure, field, value } => {
+				let fields = self.eval_struct(structure)?;
+				let value = self.eval_node(value)?;
+
+				fields.borrow_mut().insert(field.clone(), value);
+				Ok(None)
+			},
+			StatementKind::Return(Some(node)) => {
+				Ok(Some(ControlFlow::Return(self.eval_node(node)?)))
+			},
+			StatementKind::Return(None) => Ok(Some(ControlFlow::Return(Value::None))),
+			StatementKind::If { condition, branch, else_branch } => {
 				match self.eval_node(condition)? {
 					Value::Boolean(val) => match val {
-						true => Ok(self.eval_statements(branch)?),
-						false => Ok(self.eval_statements(else_branch)?)
+						true => Ok(self.eval_block(branch)?),
+						false => Ok(self.eval_block(else_branch)?)
 					},
-					_ => Err(RuntimeResult::TypeError("if condition must be boolean"))
+					_ => Err(RuntimeResult::TypeError("if condition must be boolean").into())
 				}
 			},
-			Statement::While { condition, loop_statements } => {
+			StatementKind::While { label, condition, loop_statements } => {
 				while match self.eval_node(condition)? {
 					Value::Boolean(val) => val,
-					_ => return Err(RuntimeResult::TypeError("while condition must be boolean"))
+					_ => return Err(RuntimeResult::TypeError("while condition must be boolean").into())
 				} {
-					match self.eval_statements(loop_statements)? {
-						Some(val) => {
-							return Ok(Some(val));
+					match self.eval_block(loop_statements)? {
+						Some(ControlFlow::Break(target)) if targets_loop(label, &target) => break,
+						Some(ControlFlow::Continue(target)) if targets_loop(label, &target) => (),
+						Some(flow) => {
+							return Ok(Some(flow));
 						},
 						None => ()
 					};
@@ -200,187 +467,438 @@ impl<'a> FunctionState<'a> {
 
 				return Ok(None);
 			},
-			Statement::InfiniteLoop(statements) => {
+			StatementKind::InfiniteLoop { label, loop_statements } => {
 				loop {
-					match self.eval_statements(statements)? {
-						Some(val) => {
-							return Ok(Some(val));
+					match self.eval_block(loop_statements)? {
+						Some(ControlFlow::Break(target)) if targets_loop(label, &target) => break,
+						Some(ControlFlow::Continue(target)) if targets_loop(label, &target) => (),
+						Some(flow) => {
+							return Ok(Some(flow));
 						},
 						None => ()
 					};
 				}
-			}
-		}
-	}
 
-	fn eval_node(&mut self, node: &Node) -> Result<Value, RuntimeResult> {
-		match node {
-			Node::Int(val) => Ok(Value::Integer(*val)),
-			Node::Add { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+				return Ok(None);
+			},
+			StatementKind::For { label, variable, start, end, inclusive, step, loop_statements } => {
+				let (mut i, end, int_type) = self.eval_integers(start, end, "range bounds must be integers")?;
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => {
-						match l.checked_add(r) {
-							Some(res) => Ok(Value::Integer(res)),
-							None => Err(RuntimeResult::AdditionOverflow)
-						}
-					},
-					_ => Err(RuntimeResult::TypeError("cannot add non-integers"))
+				while match inclusive {
+					true => i <= end,
+					false => i < end
+				} {
+					self.scopes.push(HashMap::from([(variable.clone(), Value::wrapping_from_integer(i, int_type))]));
+					let flow = self.eval_block(loop_statements);
+					self.scopes.pop();
+
+					match flow? {
+						Some(ControlFlow::Break(target)) if targets_loop(label, &target) => break,
+						Some(ControlFlow::Continue(target)) if targets_loop(label, &target) => (),
+						Some(flow) => {
+							return Ok(Some(flow));
+						},
+						None => ()
+					};
+
+					// stepping past the largest value of the type means the range is exhausted
+					i += *step as i128;
+					if i > int_type.max() {
+						break;
+					}
 				}
+
+				return Ok(None);
 			},
-			Node::Subtract { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+			StatementKind::Match { value, arms } => {
+				let value = self.eval_node(value)?;
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => {
-						match l.checked_sub(r) {
-							Some(res) => Ok(Value::Integer(res)),
-							None => Err(RuntimeResult::SubtractionOverflow)
-						}
-					},
-					_ => Err(RuntimeResult::TypeError("cannot subtract non-integers"))
+				for MatchArm { pattern, statements, .. } in arms {
+					if let Some(bindings) = self.match_pattern(pattern, &value)? {
+						self.scopes.push(bindings);
+						let flow = self.eval_block(statements);
+						self.scopes.pop();
+						return flow;
+					}
 				}
+
+				Err(RuntimeResult::TypeError("match must cover every value").into())
 			},
-			Node::Multiply { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+			StatementKind::Break(label) => Ok(Some(ControlFlow::Break(label.clone()))),
+			StatementKind::Continue(label) => Ok(Some(ControlFlow::Continue(label.clone())))
+		}
+	}
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => {
-						match l.checked_mul(r) {
-							Some(res) => Ok(Value::Integer(res)),
-							None => Err(RuntimeResult::MultiplicationOverflow)
-						}
-					},
-					_ => Err(RuntimeResult::TypeError("cannot multiply non-integers"))
+	// the typecheck pass guarantees that both operands of an integer operator have the same type
+	fn eval_integers(&mut self, lhs: &Node, rhs: &Node, error: &'static str) -> Result<(i128, i128, IntegerType), RuntimeError> {
+		match (self.eval_node(lhs)?.to_integer(), self.eval_node(rhs)?.to_integer()) {
+			(Some((l, l_type)), Some((r, r_type))) if l_type == r_type => Ok((l, r, l_type)),
+			_ => Err(RuntimeResult::TypeError(error).into())
+		}
+	}
+
+	fn eval_numbers(&mut self, lhs: &Node, rhs: &Node, error: &'static str) -> Result<Numbers, RuntimeError> {
+		let lhv = self.eval_node(lhs)?;
+		let rhv = self.eval_node(rhs)?;
+
+		match (&lhv, &rhv, lhv.to_integer(), rhv.to_integer()) {
+			(Value::Float(l), Value::Float(r), _, _) => Ok(Numbers::Floats(*l, *r)),
+			(_, _, Some((l, l_type)), Some((r, r_type))) if l_type == r_type => Ok(Numbers::Integers(l, r, l_type)),
+			_ => Err(RuntimeResult::TypeError(error).into())
+		}
+	}
+
+	fn eval_array_index(&mut self, array: &Node, index: &Node) -> Result<(SharedArray, i32), RuntimeError> {
+		let elements = match self.eval_node(array)? {
+			Value::Array(_, elements) => elements,
+			_ => return Err(RuntimeResult::TypeError("can only index into arrays").into())
+		};
+
+		match self.eval_node(index)? {
+			Value::I32(index) => Ok((elements, index)),
+			_ => Err(RuntimeResult::TypeError("array indices must be i32").into())
+		}
+	}
+
+	fn eval_struct(&mut self, structure: &Node) -> Result<SharedStruct, RuntimeError> {
+		match self.eval_node(structure)? {
+			Value::Struct(_, fields) => Ok(fields),
+			_ => Err(RuntimeResult::TypeError("can only access fields of structs").into())
+		}
+	}
+
+	// returns the variables bound by the pattern, or None if it doesn't match the value
+	fn match_pattern(&mut self, pattern: &Pattern, value: &Value) -> Result<Option<HashMap<String, Value>>, RuntimeError> {
+		let matches = match (pattern, value) {
+			(Pattern::Wildcard, _) => true,
+			(Pattern::Literal(literal), value) => match (&self.eval_node(literal)?, value) {
+				(Value::String(l), Value::String(r)) => l == r,
+				(Value::Boolean(l), Value::Boolean(r)) => l == r,
+				(literal, value) => match (literal.to_integer(), value.to_integer()) {
+					(Some(l), Some(r)) => l == r,
+					_ => return Err(RuntimeResult::TypeError("pattern must be the same type as the matched value").into())
 				}
 			},
-			Node::Divide { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+			(Pattern::Variant { variant, bindings, .. }, Value::Enum(_, value_variant, payload)) if variant == value_variant => {
+				let bound = bindings.iter().zip(payload).filter_map(|(binding, value)| {
+					binding.as_ref().map(|name| (name.clone(), value.clone()))
+				}).collect();
+				return Ok(Some(bound));
+			},
+			(Pattern::Variant { .. }, Value::Enum(..)) => false,
+			_ => return Err(RuntimeResult::TypeError("pattern must be the same type as the matched value").into())
+		};
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => {
-						match l.checked_div(r) {
-							Some(res) => Ok(Value::Integer(res)),
-							None => Err(RuntimeResult::DivisionError)
-						}
-					},
-					_ => Err(RuntimeResult::TypeError("cannot divide non-integers"))
+		match matches {
+			true => Ok(Some(HashMap::new())),
+			false => Ok(None)
+		}
+	}
+
+	fn eval_node(&mut self, node: &Node) -> Result<Value, RuntimeError> {
+		let value = match &node.kind {
+			// an empty array literal only knows its element type from the typecheck pass
+			NodeKind::Array(elements) => match node.data_type() {
+				AbstractType::Array(element_type) => self.eval_array(elements, element_type),
+				_ => Err(RuntimeResult::TypeError("array literals must have an array type").into())
+			},
+			NodeKind::Closure { name, captures, .. } => {
+				let mut values = Vec::with_capacity(captures.len());
+				for capture in captures {
+					match self.lookup(&capture.name) {
+						Some(value) => values.push(value.clone()),
+						None => return Err(RuntimeResult::UndefinedVariable(capture.name.clone()).into())
+					}
 				}
+
+				Ok(Value::Function(name.clone(), values, node.data_type().clone()))
 			},
-			Node::Equal { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+			// a name that isn't a variable is a function, whose signature only the typecheck pass knows
+			NodeKind::Get { name } if self.lookup(name).is_none() && matches!(node.data_type, Some(AbstractType::Function { .. })) => {
+				Ok(Value::Function(name.clone(), vec![], node.data_type().clone()))
+			},
+			kind => self.eval_node_kind(kind)
+		};
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l == r)),
-					_ => Err(RuntimeResult::TypeError("cannot check equality of non-integers"))
+		value.map_err(|err| err.or_at(node.span))
+	}
+
+	fn eval_array(&mut self, elements: &Vec<Node>, element_type: &AbstractType) -> Result<Value, RuntimeError> {
+		let mut values = Vec::with_capacity(elements.len());
+		for element in elements {
+			values.push(self.eval_node(element)?);
+		}
+
+		Ok(Value::Array(element_type.clone(), Rc::new(RefCell::new(values))))
+	}
+
+	fn eval_node_kind(&mut self, node: &NodeKind) -> Result<Value, RuntimeError> {
+		match node {
+			NodeKind::Int { value, int_type, .. } => Ok(Value::wrapping_from_integer(*value, *int_type)),
+			NodeKind::Float(value) => Ok(Value::Float(*value)),
+			NodeKind::Add { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot add non-numbers")? {
+					Numbers::Integers(l, r, int_type) => checked_integer(l.checked_add(r), int_type, RuntimeResult::AdditionOverflow),
+					Numbers::Floats(l, r) => Ok(Value::Float(l + r))
 				}
 			},
-			Node::NotEqual { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
-
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l != r)),
-					_ => Err(RuntimeResult::TypeError("cannot check non-equality of non-integers"))
+			NodeKind::Subtract { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot subtract non-numbers")? {
+					Numbers::Integers(l, r, int_type) => checked_integer(l.checked_sub(r), int_type, RuntimeResult::SubtractionOverflow),
+					Numbers::Floats(l, r) => Ok(Value::Float(l - r))
 				}
 			},
-			Node::LessThanOrEqual { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
-
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l <= r)),
-					_ => Err(RuntimeResult::TypeError("cannot check less than or equal of non-integers"))
+			NodeKind::Multiply { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot multiply non-numbers")? {
+					Numbers::Integers(l, r, int_type) => checked_integer(l.checked_mul(r), int_type, RuntimeResult::MultiplicationOverflow),
+					Numbers::Floats(l, r) => Ok(Value::Float(l * r))
 				}
 			},
-			Node::GreaterThanOrEqual { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+			NodeKind::Divide { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot divide non-numbers")? {
+					Numbers::Integers(l, r, int_type) => checked_integer(l.checked_div(r), int_type, RuntimeResult::DivisionError),
+					Numbers::Floats(l, r) => Ok(Value::Float(l / r))
+				}
+			},
+			NodeKind::Remainder { lhs, rhs } => {
+				let (l, r, int_type) = self.eval_integers(lhs, rhs, "cannot calculate the remainder of non-integers")?;
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l >= r)),
-					_ => Err(RuntimeResult::TypeError("cannot check greater than or equal of non-integers"))
+				// the remainder is only defined when the quotient is
+				match checked_integer(l.checked_div(r), int_type, RuntimeResult::RemainderError) {
+					Ok(_) => Ok(Value::wrapping_from_integer(l % r, int_type)),
+					Err(err) => Err(err)
 				}
 			},
-			Node::LessThan { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+			NodeKind::BitAnd { lhs, rhs } => {
+				let (l, r, int_type) = self.eval_integers(lhs, rhs, "cannot apply bitwise and to non-integers")?;
+				Ok(Value::wrapping_from_integer(l & r, int_type))
+			},
+			NodeKind::BitOr { lhs, rhs } => {
+				let (l, r, int_type) = self.eval_integers(lhs, rhs, "cannot apply bitwise or to non-integers")?;
+				Ok(Value::wrapping_from_integer(l | r, int_type))
+			},
+			NodeKind::BitXor { lhs, rhs } => {
+				let (l, r, int_type) = self.eval_integers(lhs, rhs, "cannot apply bitwise xor to non-integers")?;
+				Ok(Value::wrapping_from_integer(l ^ r, int_type))
+			},
+			NodeKind::ShiftLeft { lhs, rhs } => {
+				let (l, r, int_type) = self.eval_integers(lhs, rhs, "cannot shift non-integers")?;
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l < r)),
-					_ => Err(RuntimeResult::TypeError("cannot check less than of non-integers"))
+				match r >= 0 && r < int_type.bits() as i128 {
+					true => Ok(Value::wrapping_from_integer(l << r, int_type)),
+					false => Err(RuntimeResult::ShiftOverflow.into())
 				}
 			},
-			Node::GreaterThan { lhs, rhs } => {
-				let lhv = self.eval_node(lhs)?;
-				let rhv = self.eval_node(rhs)?;
+			NodeKind::ShiftRight { lhs, rhs } => {
+				let (l, r, int_type) = self.eval_integers(lhs, rhs, "cannot shift non-integers")?;
 
-				match (lhv, rhv) {
-					(Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l > r)),
-					_ => Err(RuntimeResult::TypeError("cannot check greater than of non-integers"))
+				// unsigned values are never negative once widened, so this is a logical shift for them
+				match r >= 0 && r < int_type.bits() as i128 {
+					true => Ok(Value::wrapping_from_integer(l >> r, int_type)),
+					false => Err(RuntimeResult::ShiftOverflow.into())
+				}
+			},
+			NodeKind::Equal { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot check equality of non-numbers")? {
+					Numbers::Integers(l, r, _) => Ok(Value::Boolean(l == r)),
+					Numbers::Floats(l, r) => Ok(Value::Boolean(l == r))
+				}
+			},
+			NodeKind::NotEqual { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot check non-equality of non-numbers")? {
+					Numbers::Integers(l, r, _) => Ok(Value::Boolean(l != r)),
+					Numbers::Floats(l, r) => Ok(Value::Boolean(l != r))
+				}
+			},
+			NodeKind::LessThanOrEqual { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot check less than or equal of non-numbers")? {
+					Numbers::Integers(l, r, _) => Ok(Value::Boolean(l <= r)),
+					Numbers::Floats(l, r) => Ok(Value::Boolean(l <= r))
+				}
+			},
+			NodeKind::GreaterThanOrEqual { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot check greater than or equal of non-numbers")? {
+					Numbers::Integers(l, r, _) => Ok(Value::Boolean(l >= r)),
+					Numbers::Floats(l, r) => Ok(Value::Boolean(l >= r))
+				}
+			},
+			NodeKind::LessThan { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot check less than of non-numbers")? {
+					Numbers::Integers(l, r, _) => Ok(Value::Boolean(l < r)),
+					Numbers::Floats(l, r) => Ok(Value::Boolean(l < r))
+				}
+			},
+			NodeKind::GreaterThan { lhs, rhs } => {
+				match self.eval_numbers(lhs, rhs, "cannot check greater than of non-numbers")? {
+					Numbers::Integers(l, r, _) => Ok(Value::Boolean(l > r)),
+					Numbers::Floats(l, r) => Ok(Value::Boolean(l > r))
+				}
+			},
+			NodeKind::Negate(operand) => {
+				let value = self.eval_node(operand)?;
+				match (&value, value.to_integer()) {
+					(Value::Float(val), _) => Ok(Value::Float(-val)),
+					(_, Some((val, int_type))) => checked_integer(Some(-val), int_type, RuntimeResult::NegationOverflow),
+					_ => Err(RuntimeResult::TypeError("cannot negate non-numbers").into())
+				}
+			},
+			NodeKind::Cast { value, target } => {
+				let value = self.eval_node(value)?;
+				match (&value, value.to_integer(), target) {
+					(Value::Float(val), _, AbstractType::Float) => Ok(Value::Float(*val)),
+					// saturates and turns NaN into 0, and no f64 is outside the range of i128 once saturated
+					(Value::Float(val), _, AbstractType::Integer(int_type)) => {
+						Ok(Value::wrapping_from_integer((*val as i128).clamp(int_type.min(), int_type.max()), *int_type))
+					},
+					(_, Some((val, _)), AbstractType::Float) => Ok(Value::Float(val as f64)),
+					(_, Some((val, _)), AbstractType::Integer(int_type)) => Ok(Value::wrapping_from_integer(val, *int_type)),
+					_ => Err(RuntimeResult::TypeError("can only cast between numbers").into())
 				}
 			},
-			Node::Bool(val) => Ok(Value::Boolean(*val)),
-			Node::BoolEqual { lhs, rhs } => {
+			NodeKind::Bool(val) => Ok(Value::Boolean(*val)),
+			NodeKind::BoolEqual { lhs, rhs } => {
 				let lhv = self.eval_node(lhs)?;
 				let rhv = self.eval_node(rhs)?;
 
 				match (lhv, rhv) {
 					(Value::Boolean(l), Value::Boolean(r)) => Ok(Value::Boolean(l == r)),
-					_ => Err(RuntimeResult::TypeError("cannot check equality of non-booleans"))
+					_ => Err(RuntimeResult::TypeError("cannot check equality of non-booleans").into())
 				}
 			},
-			Node::BoolNotEqual { lhs, rhs } => {
+			NodeKind::BoolNotEqual { lhs, rhs } => {
 				let lhv = self.eval_node(lhs)?;
 				let rhv = self.eval_node(rhs)?;
 
 				match (lhv, rhv) {
 					(Value::Boolean(l), Value::Boolean(r)) => Ok(Value::Boolean(l != r)),
-					_ => Err(RuntimeResult::TypeError("cannot check non-equality of non-booleans"))
+					_ => Err(RuntimeResult::TypeError("cannot check non-equality of non-booleans").into())
+				}
+			},
+			NodeKind::Not(operand) => {
+				match self.eval_node(operand)? {
+					Value::Boolean(val) => Ok(Value::Boolean(!val)),
+					_ => Err(RuntimeResult::TypeError("cannot invert non-booleans").into())
 				}
 			},
-			Node::StringLiteral(val) => Ok(Value::String(val.to_string())),
-			Node::Set { name, var_type, value } => {
+			NodeKind::And { lhs, rhs } => {
+				match self.eval_node(lhs)? {
+					Value::Boolean(false) => Ok(Value::Boolean(false)),
+					Value::Boolean(true) => match self.eval_node(rhs)? {
+						Value::Boolean(r) => Ok(Value::Boolean(r)),
+						_ => Err(RuntimeResult::TypeError("cannot and non-booleans").into())
+					},
+					_ => Err(RuntimeResult::TypeError("cannot and non-booleans").into())
+				}
+			},
+			NodeKind::Or { lhs, rhs } => {
+				match self.eval_node(lhs)? {
+					Value::Boolean(true) => Ok(Value::Boolean(true)),
+					Value::Boolean(false) => match self.eval_node(rhs)? {
+						Value::Boolean(r) => Ok(Value::Boolean(r)),
+						_ => Err(RuntimeResult::TypeError("cannot or non-booleans").into())
+					},
+					_ => Err(RuntimeResult::TypeError("cannot or non-booleans").into())
+				}
+			},
+			NodeKind::StringLiteral(val) => Ok(Value::String(val.to_string())),
+			NodeKind::Interpolation(parts) => {
+				let mut result = String::new();
+				for part in parts {
+					// formatted by the same functions as in compiled code, which take integers widened to 64 bits
+					let (name, arg) = match self.eval_node(part)? {
+						Value::String(val) => {
+							result.push_str(&val);
+							continue;
+						},
+						Value::Float(val) => ("float_to_str", Value::Float(val)),
+						Value::Boolean(val) => ("bool_to_str", Value::Boolean(val)),
+						val => match val.to_integer() {
+							Some((val, int_type)) if int_type.is_signed() => ("i64_to_str", Value::I64(val as i64)),
+							Some((val, _)) => ("u64_to_str", Value::U64(val as u64)),
+							None => return Err(RuntimeResult::TypeError("only integers, floats, bools and strings can be interpolated").into())
+						}
+					};
+
+					match try_std_function(name, &[arg], self.output_string)? {
+						Some(Value::String(val)) => result.push_str(&val),
+						_ => unreachable!("formatting functions return strings")
+					}
+				}
+
+				Ok(Value::String(result))
+			},
+			NodeKind::Array(_) => unreachable!("array literals are evaluated by eval_node"),
+			NodeKind::Closure { .. } => unreachable!("closures are evaluated by eval_node"),
+			NodeKind::Construct { name, fields } => {
+				let mut values = HashMap::new();
+				for (field, value) in fields {
+					values.insert(field.clone(), self.eval_node(value)?);
+				}
+
+				Ok(Value::Struct(name.clone(), Rc::new(RefCell::new(values))))
+			},
+			NodeKind::Field { structure, field } => {
+				match self.eval_struct(structure)?.borrow().get(field) {
+					Some(value) => Ok(value.clone()),
+					None => Err(RuntimeResult::TypeError("struct has no such field").into())
+				}
+			},
+			NodeKind::Variant { enum_name, variant, values } => {
+				let mut payload = Vec::with_capacity(values.len());
+				for value in values {
+					payload.push(self.eval_node(value)?);
+				}
+
+				Ok(Value::Enum(enum_name.clone(), variant.clone(), payload))
+			},
+			NodeKind::Index { array, index } => {
+				let (elements, index) = self.eval_array_index(array, index)?;
+				let elements = elements.borrow();
+				Ok(elements[element_index(&elements, index)?].clone())
+			},
+			NodeKind::Set { name, var_type, value } => {
 				let value = self.eval_node(value)?;
-				if value.to_abstract() != *var_type {
-					return Err(RuntimeResult::TypeError("value must be same type as variable is declared"))
+				if Some(value.to_abstract()) != *var_type {
+					return Err(RuntimeResult::TypeError("value must be same type as variable is declared").into())
 				}
 
-				self.variables.insert(name.to_string(), value.clone()); // clone = bad
+				self.scopes.last_mut().unwrap().insert(name.to_string(), value.clone()); // clone = bad
 				Ok(value) // for compatability with native/jit
 			},
-			Node::Get { name } => {
-				match self.variables.get(name) {
+			NodeKind::Get { name } => {
+				match self.lookup(name) {
 					Some(val) => Ok(val.clone()), // clone = bad
-					None => Err(RuntimeResult::UndefinedVariable(name.to_string()))
+					None => Err(RuntimeResult::UndefinedVariable(name.to_string()).into())
 				}
 			},
-			Node::Function { name, args } => {
+			NodeKind::Function { name, args } => {
 				let mut evaluated_args: Vec<Value> = vec![];
 				for arg in args {
 					evaluated_args.push(self.eval_node(arg)?)
 				}
 
-				match try_std_function(name, &evaluated_args, &mut self.output_string)? {
+				// variables shadow functions, so a variable with the name is a function value
+				let (name, captured) = match self.lookup(name) {
+					Some(Value::Function(target, captured, _)) => (target.clone(), captured.clone()),
+					Some(_) => return Err(RuntimeResult::TypeError("can only call functions").into()),
+					None => (name.clone(), vec![])
+				};
+
+				match try_std_function(&name, &evaluated_args, &mut self.output_string)? {
 					Some(value) => Ok(value),
 					None => {
 						let mut function_state = FunctionState {
-							info: match self.functions.get(name) {
+							info: match self.functions.get(&name) {
 								Some(info) => info,
-								None => return Err(RuntimeResult::UndefinedFunction(name.to_string()))
+								None => return Err(RuntimeResult::UndefinedFunction(name.to_string()).into())
 							},
-							variables: HashMap::new(),
+							scopes: vec![HashMap::new()],
 							functions: self.functions,
+							globals: self.globals,
 							output_string: self.output_string
 						};
 
-						Ok(function_state.run(evaluated_args)?)
+						Ok(function_state.run(captured, evaluated_args)?)
 					}
 				}
 			},
@@ -388,15 +906,71 @@ impl<'a> FunctionState<'a> {
 	}
 }
 
-fn try_std_function(name: &str, args: &Vec<Value>, output_string: &mut String) -> Result<Option<Value>, RuntimeResult> {
-	let result = match name {
-		"print_int" => {
-			match &args[0] {
-				Value::Integer(val) => {
+fn element_index(elements: &[Value], index: i32) -> Result<usize, RuntimeError> {
+	match usize::try_from(index) {
+		Ok(i) if i < elements.len() => Ok(i),
+		_ => Err(RuntimeResult::IndexOutOfBounds {
+			index,
+			length: elements.len()
+		}.into())
+	}
+}
+
+fn checked_integer(value: Option<i128>, int_type: IntegerType, overflow: RuntimeResult) -> Result<Value, RuntimeError> {
+	match value.and_then(|value| Value::from_integer(value, int_type)) {
+		Some(value) => Ok(value),
+		None => Err(overflow.into())
+	}
+}
+
+// matches the shortest round-tripping `%g` output of print_float in the C standard library
+fn format_float(value: f64) -> String {
+	if value.is_nan() {
+		return String::from("nan");
+	}
+	if value.is_infinite() {
+		return match value > 0.0 {
+			true => String::from("inf"),
+			false => String::from("-inf")
+		};
+	}
+
+	let scientific = format!("{:e}", value);
+	let (mantissa, exponent) = scientific.split_once('e').unwrap();
+	let exponent: i32 = exponent.parse().unwrap();
+	let digits = mantissa.chars().filter(|c| c.is_ascii_digit()).count() as i32;
+
+	match exponent < -4 || exponent >= digits.max(6) {
+		true => {
+			let sign = match exponent < 0 {
+				true => '-',
+				false => '+'
+			};
+			format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+		},
+		false => format!("{}", value)
+	}
+}
+
+fn try_std_function(name: &str, args: &[Value], output_string: &mut String) -> Result<Option<Value>, RuntimeError> {
+	match name {
+		"print_int" | "print_i8" | "print_i16" | "print_i64" | "print_u8" | "print_u16" | "print_u32" | "print_u64" => {
+			match args[0].to_integer() {
+				Some((val, _)) => {
 					output_string.push_str(&format!("{}\n", val));
 					Ok(Some(Value::None))
 				},
-				_ => Err(RuntimeResult::TypeError("print_int only prints integers"))
+				_ => Err(RuntimeResult::TypeError("integer print functions only print integers").into())
+			}
+		},
+		"print_float" => {
+			match &args[0] {
+				Value::Float(val) => {
+					output_string.push_str(&format_float(*val));
+					output_string.push('\n');
+					Ok(Some(Value::None))
+				},
+				_ => Err(RuntimeResult::TypeError("print_float only prints floats").into())
 			}
 		},
 		"print_bool" => {
@@ -405,7 +979,7 @@ fn try_std_function(name: &str, args: &Vec<Value>, output_string: &mut String) -
 					output_string.push_str(&format!("{}\n", val));
 					Ok(Some(Value::None))
 				},
-				_ => Err(RuntimeResult::TypeError("print_bool only prints booleans"))
+				_ => Err(RuntimeResult::TypeError("print_bool only prints booleans").into())
 			}
 		},
 		"print_str" => {
@@ -415,7 +989,22 @@ fn try_std_function(name: &str, args: &Vec<Value>, output_string: &mut String) -
 					output_string.push('\n');
 					Ok(Some(Value::None))
 				},
-				_ => Err(RuntimeResult::TypeError("print_str only prints strings"))
+				_ => Err(RuntimeResult::TypeError("print_str only prints strings").into())
+			}
+		},
+		"len" => {
+			match &args[0] {
+				Value::Array(_, elements) => Ok(Some(Value::I32(elements.borrow().len() as i32))),
+				_ => Err(RuntimeResult::TypeError("len only takes an array").into())
+			}
+		},
+		"push" => {
+			match &args[0] {
+				Value::Array(_, elements) => {
+					elements.borrow_mut().push(args[1].clone());
+					Ok(Some(Value::None))
+				},
+				_ => Err(RuntimeResult::TypeError("push only takes an array").into())
 			}
 		},
 		"str_eq" => {
@@ -423,15 +1012,15 @@ fn try_std_function(name: &str, args: &Vec<Value>, output_string: &mut String) -
 				(Value::String(l), Value::String(r)) => {
 					Ok(Some(Value::Boolean(l == r)))
 				},
-				_ => Err(RuntimeResult::TypeError("str_eq only compares strings"))
+				_ => Err(RuntimeResult::TypeError("str_eq only compares strings").into())
 			}
 		},
 		"str_len" => {
 			match &args[0] {
 				Value::String(val) => {
-					Ok(Some(Value::Integer(val.len() as i32)))
+					Ok(Some(Value::I32(val.len() as i32)))
 				},
-				_ => Err(RuntimeResult::TypeError("str_len only takes a string"))
+				_ => Err(RuntimeResult::TypeError("str_len only takes a string").into())
 			}
 		},
 		"str_concat" => {
@@ -439,55 +1028,64 @@ fn try_std_function(name: &str, args: &Vec<Value>, output_string: &mut String) -
 				(Value::String(l), Value::String(r)) => {
 					Ok(Some(Value::String(l.to_string() + r)))
 				},
-				_ => Err(RuntimeResult::TypeError("str_concat can only concatenate strings"))
+				_ => Err(RuntimeResult::TypeError("str_concat can only concatenate strings").into())
+			}
+		},
+		"i64_to_str" | "u64_to_str" => {
+			match args[0].to_integer() {
+				Some((val, _)) => Ok(Some(Value::String(val.to_string()))),
+				_ => Err(RuntimeResult::TypeError("integer formatting functions only take integers").into())
+			}
+		},
+		"float_to_str" => {
+			match &args[0] {
+				Value::Float(val) => Ok(Some(Value::String(format_float(*val)))),
+				_ => Err(RuntimeResult::TypeError("float_to_str only takes a float").into())
+			}
+		},
+		"bool_to_str" => {
+			match &args[0] {
+				Value::Boolean(val) => Ok(Some(Value::String(val.to_string()))),
+				_ => Err(RuntimeResult::TypeError("bool_to_str only takes a bool").into())
 			}
 		},
 		"assert_int_eq" => {
 			match (&args[0], &args[1]) {
-				(Value::Integer(l), Value::Integer(r)) => {
+				(Value::I32(l), Value::I32(r)) => {
 					if l != r {
-						return Err(RuntimeResult::Panic);
+						return Err(RuntimeResult::Panic.into());
 					}
 
 					Ok(Some(Value::None))
 				},
-				_ => Err(RuntimeResult::TypeError("assert_int_eq only compares integers"))
+				_ => Err(RuntimeResult::TypeError("assert_int_eq only compares integers").into())
 			}
 		},
 		"assert_bool_eq" => {
 			match (&args[0], &args[1]) {
 				(Value::Boolean(l), Value::Boolean(r)) => {
 					if l != r {
-						return Err(RuntimeResult::Panic);
+						return Err(RuntimeResult::Panic.into());
 					}
 
 					Ok(Some(Value::None))
 				},
-				_ => Err(RuntimeResult::TypeError("assert_bool_eq only compares booleans"))
+				_ => Err(RuntimeResult::TypeError("assert_bool_eq only compares booleans").into())
 			}
 		},
 		"assert_str_eq" => {
 			match (&args[0], &args[1]) {
 				(Value::String(l), Value::String(r)) => {
 					if l != r {
-						return Err(RuntimeResult::Panic);
+						return Err(RuntimeResult::Panic.into());
 					}
 
 					Ok(Some(Value::None))
 				},
-				_ => Err(RuntimeResult::TypeError("assert_str_eq only compares strings"))
+				_ => Err(RuntimeResult::TypeError("assert_str_eq only compares strings").into())
 			}
 		},
-		"panic" => Err(RuntimeResult::Panic),
+		"panic" => Err(RuntimeResult::Panic.into()),
 		_ => Ok(None)
-	};
-
-	match result {
-		// for compatability with native/jit
-		Ok(result) => Ok(result.map(|val| match val {
-			Value::None => Value::Integer(0),
-			_ => val
-		})),
-		Err(err) => Err(err)
 	}
 }
@@ -2,8 +2,14 @@
 
 pub mod parse;
 
+pub mod typecheck;
+
 mod node;
 
+mod constant;
+
+pub use node::{Span, Import, FileDescription};
+
 #[cfg(feature = "codegen")]
 mod buildnode;
 
@@ -16,57 +22,104 @@ mod buildfunc;
 #[cfg(feature = "codegen")]
 use cranelift_module::{Module, FuncId};
 
+#[cfg(feature = "codegen")]
+use cranelift_codegen::ir::Signature;
+
 #[cfg(feature = "interpreter")]
 pub mod interpreter;
 
-// TODO: declare functions first outside of this function
-// TODO: too many parameters, maybe make some of them structs?
 #[cfg(feature = "codegen")]
-fn add_function<M: Module>(module: &mut M, func_map: &mut stdlib::FuncMap, function_name: String, function_info: node::FunctionInfo, data_index: &mut u64, optimize: bool) -> FuncId {
-	use cranelift_codegen::ir::AbiParam;
-
-	let mut sig = module.make_signature();
-	function_info.args.iter().for_each(|arg| {
-		let param_type = buildnode::deabstract(&arg.data_type, &module).expect("argument cannot be void");
-		sig.params.push(AbiParam::new(param_type));
-	});
-	if let Some(return_type) = buildnode::deabstract(&function_info.return_type, &module) {
-		sig.returns.push(AbiParam::new(return_type));
-	}
+fn declare_function<M: Module>(module: &mut M, func_map: &mut stdlib::FuncMap, function_name: &str, function_info: &node::FunctionInfo) -> (FuncId, Signature) {
+	let arg_types: Vec<node::AbstractType> = function_info.args.iter().map(|arg| arg.data_type.clone()).collect();
+	let sig = buildnode::make_signature(module, &arg_types, &function_info.return_type, function_info.captures.is_some());
+
+	// closures can only be called through the values that create them
+	let linkage = match function_info.captures {
+		Some(_) => cranelift_module::Linkage::Local,
+		None => cranelift_module::Linkage::Export
+	};
 
 	let declared_function = module.declare_function(
-		&function_name,
-		cranelift_module::Linkage::Export,
+		function_name,
+		linkage,
 		&sig
 	).unwrap();
 
-	func_map.insert(function_name, declared_function);
+	func_map.insert(String::from(function_name), declared_function);
+	return (declared_function, sig);
+}
+
+// declares every function before building any of them, so that they can call each other no matter the order they are in
+#[cfg(feature = "codegen")]
+fn add_functions<M: Module>(module: &mut M, func_map: &mut stdlib::FuncMap, file_description: node::FileDescription) -> FuncId {
+	let mut functions: Vec<(String, node::FunctionInfo)> = file_description.functions.into_iter().collect();
+	functions.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+
+	// statics are set at the start of the program, in the order they are declared
+	let mut body = vec![];
+	for global in file_description.globals {
+		let data_id = module.declare_data(&format!("static.{}", global.name), cranelift_module::Linkage::Local, true, false).unwrap();
+
+		// every static has an 8 byte slot, which is enough for any type
+		let mut data_context = cranelift_module::DataContext::new();
+		data_context.define_zeroinit(8);
+		module.define_data(data_id, &data_context).unwrap();
+
+		body.push(node::Statement {
+			kind: node::StatementKind::Assign {
+				name: global.name,
+				value: global.value
+			},
+			span: global.span
+		});
+	}
+	body.extend(file_description.statements);
+
+	functions.push((String::from("zinc_main"), node::FunctionInfo {
+		body,
+		args: vec![],
+		return_type: node::AbstractType::Void,
+		span: node::Span::default(),
+		captures: None
+	}));
 
-	let func = buildfunc::build_func(sig, module, func_map, function_info.body, &function_info.args, data_index);
-	let mut context = cranelift_codegen::Context::for_function(func);
+	let declarations: Vec<(FuncId, Signature)> = functions.iter().map(|(function_name, function_info)| {
+		declare_function(module, func_map, function_name, function_info)
+	}).collect();
 
-	module.define_function(declared_function, &mut context).unwrap();
+	let mut data_index: u64 = 0;
 
-	if optimize {
-		cranelift_preopt::optimize(&mut context, module.isa()).unwrap();
+	for ((declared_function, sig), (_, function_info)) in declarations.iter().zip(functions) {
+		let func = buildfunc::build_func(*declared_function, sig.clone(), module, func_map, &file_description.types, function_info, &mut data_index);
+		let mut context = cranelift_codegen::Context::for_function(func);
+		module.define_function(*declared_function, &mut context).unwrap();
 	}
 
-	return declared_function;
+	// zinc_main is always the last function
+	return declarations.last().unwrap().0;
+}
+
+// benchmarks show statistically insignificant difference in speed, probably cranelift's fault
+// there is no cranelift_preopt pass anymore, its constant folding divides unsigned values as if they were signed (see tests/unsigned_division.zn)
+// so the optimization level set on the ISA is all that optimizing does, for the JIT as well as for objects
+#[cfg(feature = "codegen")]
+fn opt_level(optimize: bool) -> &'static str {
+	match optimize {
+		true => "speed",
+		false => "none"
+	}
 }
 
 #[cfg(feature = "native")]
-pub fn build_object<P: AsRef<std::path::Path>>(mut file_description: node::FileDescription, output_path: P, optimize: bool) {
+pub fn build_object<P: AsRef<std::path::Path>>(mut file_description: node::FileDescription, output_path: P, optimize: bool) -> Result<(), Vec<typecheck::TypeError>> {
 	use cranelift_codegen::isa;
 	use cranelift_codegen::settings::Configurable;
 	use std::io::Write;
 
-	let mut shared_builder = cranelift_codegen::settings::builder();
+	typecheck::typecheck(&mut file_description)?;
 
-	// benchmarks show statistically insignificant difference in speed, probably cranelift's fault
-	shared_builder.set("opt_level", match optimize {
-		true => "speed",
-		false => "none"
-	}).unwrap();
+	let mut shared_builder = cranelift_codegen::settings::builder();
+	shared_builder.set("opt_level", opt_level(optimize)).unwrap();
 
 	let shared_flags = cranelift_codegen::settings::Flags::new(shared_builder);
 	let isa_builder = isa::lookup(target_lexicon::Triple::host()).unwrap();
@@ -82,36 +135,42 @@ pub fn build_object<P: AsRef<std::path::Path>>(mut file_description: node::FileD
 	let mut func_map = stdlib::FuncMap::new();
 	stdlib::populate_stdlib(&mut func_map, &mut object_module);
 
-	let mut data_index: u64 = 0;
-
-	for (function_name, function) in file_description.functions.drain() {
-		add_function(&mut object_module, &mut func_map, function_name, function, &mut data_index, optimize);
-	}
-
-	add_function(&mut object_module, &mut func_map, String::from("zinc_main"), node::FunctionInfo {
-		body: file_description.statements,
-		args: vec![],
-		return_type: node::AbstractType::Void
-	}, &mut data_index, optimize);
+	add_functions(&mut object_module, &mut func_map, file_description);
 
 	let object_product = object_module.finish();
 
 	let mut file = std::fs::File::create(output_path).unwrap();
 	file.write_all(&object_product.emit().unwrap()).unwrap();
+
+	Ok(())
 }
 
 #[cfg(feature = "jit")]
-pub unsafe fn run_jit<S: Into<String>>(file_description: node::FileDescription, optimize: bool, symbols: Vec<(S, *const u8)>) {
+pub unsafe fn run_jit<S: Into<String>>(mut file_description: node::FileDescription, optimize: bool, symbols: Vec<(S, *const u8)>) -> Result<(), Vec<typecheck::TypeError>> {
+	typecheck::typecheck(&mut file_description)?;
+
 	let (jit_module, id) = jit_compile(file_description, optimize, symbols);
 	let pointer = jit_module.get_finalized_function(id);
 	let code_fn = core::mem::transmute::<_, fn()>(pointer);
 	code_fn();
 	jit_module.free_memory();
+
+	Ok(())
 }
 
 #[cfg(feature = "jit")]
-fn jit_compile<S: Into<String>>(mut file_description: node::FileDescription, optimize: bool, symbols: Vec<(S, *const u8)>) -> (cranelift_jit::JITModule, FuncId) {
-	let mut builder = cranelift_jit::JITBuilder::new(cranelift_module::default_libcall_names()).unwrap();
+fn jit_compile<S: Into<String>>(file_description: node::FileDescription, optimize: bool, symbols: Vec<(S, *const u8)>) -> (cranelift_jit::JITModule, FuncId) {
+	use cranelift_codegen::settings::Configurable;
+
+	// the same flags as JITBuilder::new, plus the optimization level
+	let mut flag_builder = cranelift_codegen::settings::builder();
+	flag_builder.set("use_colocated_libcalls", "false").unwrap();
+	flag_builder.set("is_pic", "true").unwrap();
+	flag_builder.set("opt_level", opt_level(optimize)).unwrap();
+
+	let isa_builder = cranelift_codegen::isa::lookup(target_lexicon::Triple::host()).unwrap();
+	let isa = isa_builder.finish(cranelift_codegen::settings::Flags::new(flag_builder)).unwrap();
+	let mut builder = cranelift_jit::JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
 
 	for (symbol_name, symbol_val) in symbols {
 		builder.symbol(symbol_name, symbol_val);
@@ -122,17 +181,7 @@ fn jit_compile<S: Into<String>>(mut file_description: node::FileDescription, opt
 	let mut func_map = stdlib::FuncMap::new();
 	stdlib::populate_stdlib(&mut func_map, &mut jit_module);
 
-	let mut data_index: u64 = 0;
-
-	for (function_name, function) in file_description.functions.drain() {
-		add_function(&mut jit_module, &mut func_map, function_name, function, &mut data_index, optimize);
-	}
-
-	let id = add_function(&mut jit_module, &mut func_map, String::from("zinc_main"), node::FunctionInfo {
-		body: file_description.statements,
-		args: vec![],
-		return_type: node::AbstractType::Void
-	}, &mut data_index, optimize);
+	let id = add_functions(&mut jit_module, &mut func_map, file_description);
 
 	jit_module.finalize_definitions();
 	return (jit_module, id);
@@ -147,7 +196,7 @@ fn test_jit() {
 		if (z == 5) {
 			let n: i32 = 5;
 			while (n > 0) {
-				let n: i32 = n - 1;
+				n = n - 1;
 			}
 			assert_int_eq(n, 0);
 		} else {
@@ -169,5 +218,5 @@ fn test_jit() {
 		("assert_int_eq", assert_int_eq as *const u8),
 		("panic", panic as *const u8)
 	];
-	unsafe { run_jit(file_description, true, symbols); };
+	unsafe { run_jit(file_description, true, symbols) }.expect("failed to typecheck");
 }
\ No newline at end of file
@@ -1,14 +1,184 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IntegerType {
+	I8,
+	I16,
+	I32,
+	I64,
+	U8,
+	U16,
+	U32,
+	U64
+}
+
+impl IntegerType {
+	pub fn from_name(name: &str) -> Option<IntegerType> {
+		match name {
+			"i8" => Some(IntegerType::I8),
+			"i16" => Some(IntegerType::I16),
+			"i32" => Some(IntegerType::I32),
+			"i64" => Some(IntegerType::I64),
+			"u8" => Some(IntegerType::U8),
+			"u16" => Some(IntegerType::U16),
+			"u32" => Some(IntegerType::U32),
+			"u64" => Some(IntegerType::U64),
+			_ => None
+		}
+	}
+
+	pub fn bits(&self) -> u32 {
+		match self {
+			IntegerType::I8 | IntegerType::U8 => 8,
+			IntegerType::I16 | IntegerType::U16 => 16,
+			IntegerType::I32 | IntegerType::U32 => 32,
+			IntegerType::I64 | IntegerType::U64 => 64
+		}
+	}
+
+	pub fn is_signed(&self) -> bool {
+		matches!(self, IntegerType::I8 | IntegerType::I16 | IntegerType::I32 | IntegerType::I64)
+	}
+
+	pub fn min(&self) -> i128 {
+		match self.is_signed() {
+			true => -(1 << (self.bits() - 1)),
+			false => 0
+		}
+	}
+
+	pub fn max(&self) -> i128 {
+		match self.is_signed() {
+			true => (1 << (self.bits() - 1)) - 1,
+			false => (1 << self.bits()) - 1
+		}
+	}
+}
+
+impl std::fmt::Display for IntegerType {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let prefix = match self.is_signed() {
+			true => "i",
+			false => "u"
+		};
+		write!(f, "{}{}", prefix, self.bits())
+	}
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum AbstractType {
-    Integer,
-    Boolean,
-    String,
-    Void
+	Integer(IntegerType),
+	Float,
+	Boolean,
+	String,
+	Array(Box<AbstractType>),
+	// a user-defined struct or enum, which the typecheck pass makes sure is declared
+	Named(String),
+	// a named function or a closure, which can be called like one
+	Function {
+		args: Vec<AbstractType>,
+		return_type: Box<AbstractType>
+	},
+	Void
+}
+
+impl AbstractType {
+	/// The types that are built into the language, which structs and enums cannot be named after.
+	pub fn from_builtin_name(name: &str) -> Option<AbstractType> {
+		if let Some(int_type) = IntegerType::from_name(name) {
+			return Some(AbstractType::Integer(int_type));
+		}
+
+		match name {
+			"f64" => Some(AbstractType::Float),
+			"bool" => Some(AbstractType::Boolean),
+			"str" => Some(AbstractType::String),
+			"void" => Some(AbstractType::Void),
+			_ => None
+		}
+	}
+}
+
+impl std::fmt::Display for AbstractType {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			AbstractType::Integer(int_type) => write!(f, "{}", int_type),
+			AbstractType::Float => write!(f, "f64"),
+			AbstractType::Boolean => write!(f, "bool"),
+			AbstractType::String => write!(f, "str"),
+			AbstractType::Array(element_type) => write!(f, "[{}]", element_type),
+			AbstractType::Named(name) => write!(f, "{}", name),
+			AbstractType::Function { args, return_type } => {
+				let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+				write!(f, "fn({}) -> {}", args.join(", "), return_type)
+			},
+			AbstractType::Void => write!(f, "void")
+		}
+	}
+}
+
+/// Byte offsets into the source a node was parsed from.
+/// When a program is made of several files, the offsets of every file start where the previous file's end.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize
+}
+
+impl Span {
+	pub fn to(self, other: Span) -> Span {
+		Span {
+			start: self.start,
+			end: other.end
+		}
+	}
+
+	/// 1-based line and column of the start of the span.
+	pub fn line_col(&self, source: &str) -> (usize, usize) {
+		let before = &source[..self.start];
+		let line = before.matches('\n').count() + 1;
+		let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+		let column = before[line_start..].chars().count() + 1;
+		return (line, column);
+	}
+}
+
+#[derive(Debug)]
+pub struct Node {
+	pub kind: NodeKind,
+	pub span: Span,
+	// filled in by the typecheck pass
+	pub data_type: Option<AbstractType>
+}
+
+impl Node {
+	pub fn new(kind: NodeKind, span: Span) -> Self {
+		Node {
+			kind,
+			span,
+			data_type: None
+		}
+	}
+
+	/// The type recorded by the typecheck pass.
+	pub fn data_type(&self) -> &AbstractType {
+		self.data_type.as_ref().expect("node should be typechecked")
+	}
+
+	pub fn integer_type(&self) -> IntegerType {
+		match self.data_type() {
+			AbstractType::Integer(int_type) => *int_type,
+			_ => unreachable!("node should be an integer")
+		}
+	}
 }
 
 #[derive(Debug)]
-pub enum Node {
-	Int(i32),
+pub enum NodeKind {
+	// an integer literal without a suffix is an i32, unless the typecheck pass finds it used as another integer type
+	Int {
+		value: i128,
+		int_type: IntegerType,
+		suffixed: bool
+	},
 	Add {
 		lhs: Box<Node>,
 		rhs: Box<Node>
@@ -25,6 +195,30 @@ pub enum Node {
 		lhs: Box<Node>,
 		rhs: Box<Node>
 	},
+	Remainder {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
+	BitAnd {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
+	BitOr {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
+	BitXor {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
+	ShiftLeft {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
+	ShiftRight {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
 	Equal {
 		lhs: Box<Node>,
 		rhs: Box<Node>
@@ -49,6 +243,12 @@ pub enum Node {
 		lhs: Box<Node>,
 		rhs: Box<Node>
 	},
+	Negate(Box<Node>),
+	Float(f64),
+	Cast {
+		value: Box<Node>,
+		target: AbstractType
+	},
 
 	Bool(bool),
 	BoolEqual {
@@ -59,12 +259,45 @@ pub enum Node {
 		lhs: Box<Node>,
 		rhs: Box<Node>
 	},
+	Not(Box<Node>),
+	And {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
+	Or {
+		lhs: Box<Node>,
+		rhs: Box<Node>
+	},
 
 	StringLiteral(String),
+	// a string literal with values in braces, whose parts are formatted and joined at runtime
+	Interpolation(Vec<Node>),
+
+	Array(Vec<Node>),
+	Index {
+		array: Box<Node>,
+		index: Box<Node>
+	},
+
+	Construct {
+		name: String,
+		fields: Vec<(String, Node)>
+	},
+	Field {
+		structure: Box<Node>,
+		field: String
+	},
+
+	Variant {
+		enum_name: String,
+		variant: String,
+		values: Vec<Node>
+	},
 
 	Set {
 		name: String,
-		var_type: AbstractType,
+		// None until the typecheck pass infers it, if the declaration doesn't give it
+		var_type: Option<AbstractType>,
 		value: Box<Node>
 	},
 	Get {
@@ -74,40 +307,185 @@ pub enum Node {
 	Function {
 		name: String,
 		args: Vec<Node>
+	},
+	// the typecheck pass moves the body into a function of its own called `name`
+	Closure {
+		args: Vec<Definition>,
+		return_type: AbstractType,
+		body: Vec<Statement>,
+		// filled in by the typecheck pass
+		name: String,
+		captures: Vec<Definition>
 	}
 }
 
 #[derive(Debug)]
-pub enum Statement {
+pub struct Statement {
+	pub kind: StatementKind,
+	pub span: Span
+}
+
+#[derive(Debug)]
+pub enum StatementKind {
 	Node(Node),
-	Return(Node),
+	Assign {
+		name: String,
+		value: Node
+	},
+	// assigns to an element of an array, which is shared with every other reference to it
+	AssignIndex {
+		array: Node,
+		index: Node,
+		value: Node
+	},
+	// assigns to a field of a struct, which is shared like an array is
+	AssignField {
+		structure: Node,
+		field: String,
+		value: Node
+	},
+	// a bare `return;` has no value, and can only be used in functions that return void
+	Return(Option<Node>),
 	If {
 		condition: Node,
 		branch: Vec<Statement>,
 		else_branch: Vec<Statement>
 	},
 	While {
+		label: Option<String>,
 		condition: Node,
 		loop_statements: Vec<Statement>
 	},
-	InfiniteLoop(Vec<Statement>)
+	InfiniteLoop {
+		label: Option<String>,
+		loop_statements: Vec<Statement>
+	},
+	// loops over start..end or start..=end, with a fresh `variable` bound in every iteration
+	For {
+		label: Option<String>,
+		variable: String,
+		start: Node,
+		end: Node,
+		inclusive: bool,
+		step: i32,
+		loop_statements: Vec<Statement>
+	},
+	// runs the first arm whose pattern matches the value
+	Match {
+		value: Node,
+		arms: Vec<MatchArm>
+	},
+	Break(Option<String>),
+	Continue(Option<String>)
 }
 
 #[derive(Debug)]
+pub enum Pattern {
+	Wildcard,
+	// an integer, string or bool literal, compared with the value like `==` would
+	Literal(Node),
+	// binds the payload of the variant to the names that aren't None
+	Variant {
+		enum_name: String,
+		variant: String,
+		bindings: Vec<Option<String>>
+	}
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+	pub pattern: Pattern,
+	pub statements: Vec<Statement>,
+	pub span: Span
+}
+
+#[derive(Debug, Clone)]
 pub struct Definition {
 	pub name: String,
-	pub data_type: AbstractType
+	pub data_type: AbstractType,
+	pub span: Span
 }
 
 #[derive(Debug)]
 pub struct FunctionInfo {
 	pub body: Vec<Statement>,
 	pub args: Vec<Definition>,
-	pub return_type: AbstractType
+	pub return_type: AbstractType,
+	pub span: Span,
+	// the variables a closure captured, which it is called with in an environment
+	pub captures: Option<Vec<Definition>>
+}
+
+#[derive(Debug)]
+pub struct StructInfo {
+	// in declaration order, which is also the order of the fields in memory
+	pub fields: Vec<Definition>,
+	pub span: Span
+}
+
+#[derive(Debug)]
+pub struct VariantInfo {
+	pub name: String,
+	pub fields: Vec<AbstractType>,
+	pub span: Span
+}
+
+#[derive(Debug)]
+pub struct EnumInfo {
+	// in declaration order, which also gives every variant its tag
+	pub variants: Vec<VariantInfo>,
+	pub span: Span
+}
+
+impl EnumInfo {
+	pub fn variant(&self, name: &str) -> Option<(usize, &VariantInfo)> {
+		self.variants.iter().enumerate().find(|(_, variant)| variant.name == name)
+	}
 }
 
+/// A struct or enum declared by the program.
 #[derive(Debug)]
+pub enum TypeInfo {
+	Struct(StructInfo),
+	Enum(EnumInfo)
+}
+
+impl TypeInfo {
+	pub fn span(&self) -> Span {
+		match self {
+			TypeInfo::Struct(info) => info.span,
+			TypeInfo::Enum(info) => info.span
+		}
+	}
+}
+
+pub type TypeMap = std::collections::HashMap<String, TypeInfo>;
+
+/// An `import "path";` statement, which is resolved relative to the file it is in.
+#[derive(Debug)]
+pub struct Import {
+	pub path: String,
+	pub span: Span
+}
+
+/// A `const` or `static` declared at the top level of a file.
+#[derive(Debug)]
+pub struct GlobalInfo {
+	pub name: String,
+	pub data_type: AbstractType,
+	// evaluated before the program runs for a const, and at the start of the program for a static
+	pub value: Node,
+	pub constant: bool,
+	pub span: Span
+}
+
+#[derive(Debug, Default)]
 pub struct FileDescription {
 	pub statements: Vec<Statement>,
-	pub functions: std::collections::HashMap<String, FunctionInfo>
+	pub functions: std::collections::HashMap<String, FunctionInfo>,
+	pub types: TypeMap,
+	// in declaration order, which is the order statics are initialized in
+	pub globals: Vec<GlobalInfo>,
+	// the files this one imports, which are merged into it before typechecking
+	pub imports: Vec<Import>
 }
@@ -1,44 +1,215 @@
-use crate::node::{AbstractType, Node, Statement, FunctionInfo, Definition, FileDescription};
+use crate::node::{AbstractType, IntegerType, Node, NodeKind, Statement, StatementKind, FunctionInfo, StructInfo, EnumInfo, VariantInfo, TypeInfo, TypeMap, Definition, Pattern, MatchArm, Import, GlobalInfo, FileDescription, Span};
 use std::collections::HashMap;
 
 use pest::Parser;
+use pest::pratt_parser::{PrattParser, Op, Assoc};
 
 #[derive(pest_derive::Parser)]
 #[grammar = "zinc.pest"]
 struct ZincParser;
 
-fn nodeify(pair: pest::iterators::Pair<Rule>) -> Node {
-	match pair.as_rule() {
+#[derive(Debug)]
+pub enum ParseError {
+	Grammar {
+		message: String,
+		span: Span
+	},
+	IntegerOutOfBounds {
+		literal: String,
+		int_type: IntegerType,
+		span: Span
+	},
+	ZeroStep {
+		span: Span
+	},
+	InvalidEscape {
+		escape: String,
+		span: Span
+	},
+	NulInString {
+		span: Span
+	},
+	ReservedKeyword {
+		keyword: String,
+		span: Span
+	},
+	DuplicateFunction {
+		name: String,
+		span: Span
+	},
+	DuplicateType {
+		name: String,
+		span: Span
+	},
+	DuplicateGlobal {
+		name: String,
+		span: Span
+	},
+	// only the file a program is run from can have top level statements
+	StatementInImport {
+		span: Span
+	},
+	UnclosedBrace {
+		span: Span
+	},
+	UnmatchedBrace {
+		span: Span
+	},
+	InterpolationNotAllowed {
+		context: &'static str,
+		span: Span
+	}
+}
+
+impl ParseError {
+	pub fn span(&self) -> Span {
+		match self {
+			ParseError::Grammar { span, .. } => *span,
+			ParseError::IntegerOutOfBounds { span, .. } => *span,
+			ParseError::ZeroStep { span } => *span,
+			ParseError::InvalidEscape { span, .. } => *span,
+			ParseError::NulInString { span } => *span,
+			ParseError::ReservedKeyword { span, .. } => *span,
+			ParseError::DuplicateFunction { span, .. } => *span,
+			ParseError::DuplicateType { span, .. } => *span,
+			ParseError::DuplicateGlobal { span, .. } => *span,
+			ParseError::StatementInImport { span } => *span,
+			ParseError::UnclosedBrace { span } => *span,
+			ParseError::UnmatchedBrace { span } => *span,
+			ParseError::InterpolationNotAllowed { span, .. } => *span
+		}
+	}
+}
+
+impl std::fmt::Display for ParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			ParseError::Grammar { message, .. } => write!(f, "syntax error, {}", message),
+			ParseError::IntegerOutOfBounds { literal, int_type, .. } => write!(f, "integer literal `{}` does not fit in `{}`", literal, int_type),
+			ParseError::ZeroStep { .. } => write!(f, "for loop step cannot be zero"),
+			ParseError::InvalidEscape { escape, .. } => write!(f, "invalid escape `{}` in string literal", escape),
+			ParseError::NulInString { .. } => write!(f, "string literals cannot contain a NUL character, compiled programs would end the string there"),
+			ParseError::ReservedKeyword { keyword, .. } => write!(f, "`{}` is a reserved keyword and cannot be used as a name", keyword),
+			ParseError::DuplicateFunction { name, .. } => write!(f, "function `{}` is defined more than once", name),
+			ParseError::DuplicateType { name, .. } => write!(f, "type `{}` is defined more than once", name),
+			ParseError::DuplicateGlobal { name, .. } => write!(f, "global `{}` is defined more than once", name),
+			ParseError::StatementInImport { .. } => write!(f, "an imported file can only declare functions, types and globals"),
+			ParseError::UnclosedBrace { .. } => write!(f, "unclosed `{{` in string literal, use `{{{{` for a brace on its own"),
+			ParseError::UnmatchedBrace { .. } => write!(f, "unmatched `}}` in string literal, use `}}}}` for a brace on its own"),
+			ParseError::InterpolationNotAllowed { context, .. } => write!(f, "values cannot be put in the string of {}", context)
+		}
+	}
+}
+
+fn to_grammar_error(error: pest::error::Error<Rule>, offset: usize) -> ParseError {
+	let (start, end) = match error.location {
+		pest::error::InputLocation::Pos(pos) => (pos, pos),
+		pest::error::InputLocation::Span(span) => span
+	};
+
+	ParseError::Grammar {
+		message: error.variant.message().to_string(),
+		span: Span { start: offset + start, end: offset + end }
+	}
+}
+
+// converts a span of the file being parsed, which starts at byte `offset` of the program
+fn to_span(span: pest::Span, offset: usize) -> Span {
+	Span {
+		start: offset + span.start(),
+		end: offset + span.end()
+	}
+}
+
+// operators are listed from lowest to highest precedence
+fn pratt_parser() -> &'static PrattParser<Rule> {
+	static PRATT_PARSER: std::sync::OnceLock<PrattParser<Rule>> = std::sync::OnceLock::new();
+
+	PRATT_PARSER.get_or_init(|| {
+		PrattParser::new()
+			.op(Op::infix(Rule::or, Assoc::Left))
+			.op(Op::infix(Rule::and, Assoc::Left))
+			.op(Op::infix(Rule::equal, Assoc::Left) | Op::infix(Rule::not_equal, Assoc::Left)
+				| Op::infix(Rule::bool_equal, Assoc::Left) | Op::infix(Rule::bool_not_equal, Assoc::Left))
+			.op(Op::infix(Rule::less_than_or_equal, Assoc::Left) | Op::infix(Rule::greater_than_or_equal, Assoc::Left)
+				| Op::infix(Rule::less_than, Assoc::Left) | Op::infix(Rule::greater_than, Assoc::Left))
+			.op(Op::infix(Rule::bit_or, Assoc::Left))
+			.op(Op::infix(Rule::bit_xor, Assoc::Left))
+			.op(Op::infix(Rule::bit_and, Assoc::Left))
+			.op(Op::infix(Rule::shift_left, Assoc::Left) | Op::infix(Rule::shift_right, Assoc::Left))
+			.op(Op::infix(Rule::add, Assoc::Left) | Op::infix(Rule::subtract, Assoc::Left))
+			.op(Op::infix(Rule::multiply, Assoc::Left) | Op::infix(Rule::divide, Assoc::Left) | Op::infix(Rule::remainder, Assoc::Left))
+			.op(Op::postfix(Rule::cast))
+			.op(Op::prefix(Rule::negate) | Op::prefix(Rule::not))
+			.op(Op::postfix(Rule::index) | Op::postfix(Rule::field_access))
+	})
+}
+
+fn nodeify(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<Node, ParseError> {
+	let span = to_span(pair.as_span(), offset);
+
+	let kind = match pair.as_rule() {
 		Rule::expr => {
-			nodeify(pair.into_inner().next().unwrap())
+			return nodeify(pair.into_inner().next().unwrap(), offset);
 		},
 		Rule::var_declaration => {
 			let mut inner = pair.into_inner();
-			let mut declaration = inner.next().unwrap().into_inner();
+			let declaration = inner.next().unwrap();
+
+			let (name, var_type) = match declaration.as_rule() {
+				Rule::variable_definition => {
+					let mut declaration = declaration.into_inner();
+					(declaration.next().unwrap(), Some(to_abstract_type(declaration.next().unwrap())))
+				},
+				_ => (declaration, None)
+			};
 
-			Node::Set {
-				name: String::from(declaration.next().unwrap().as_str()),
-				var_type: to_abstract_type(declaration.next().unwrap().as_str()),
-				value: Box::new(nodeify(inner.next().unwrap()))
+			NodeKind::Set {
+				name: to_name(name, offset)?,
+				var_type,
+				value: Box::new(nodeify(inner.next().unwrap(), offset)?)
 			}
 		},
 		Rule::identifier => {
 			let name = String::from(pair.as_str());
 
-			Node::Get {
+			NodeKind::Get {
 				name
 			}
 		},
 		Rule::operand => {
-			nodeify(pair.into_inner().next().unwrap())
+			return nodeify(pair.into_inner().next().unwrap(), offset);
 		},
 		Rule::number => {
-			Node::Int(
-				pair.as_str().parse().expect("int out of bounds")
-			)
+			let literal = pair.as_str();
+			let mut inner = pair.into_inner();
+			let digits = inner.next().unwrap().as_str();
+
+			// unsuffixed literals are i32 until the typecheck pass gives them the type they are used as, and checks their range then
+			let (int_type, suffixed) = match inner.next() {
+				Some(suffix) => (IntegerType::from_name(suffix.as_str()).unwrap(), true),
+				None => (IntegerType::I32, false)
+			};
+
+			match digits.parse::<i128>() {
+				Ok(value) if !suffixed || (value >= int_type.min() && value <= int_type.max()) => NodeKind::Int {
+					value,
+					int_type,
+					suffixed
+				},
+				_ => return Err(ParseError::IntegerOutOfBounds {
+					literal: String::from(literal),
+					int_type,
+					span
+				})
+			}
+		},
+		Rule::float => {
+			// the grammar only accepts literals that rust can parse, and out of range ones become infinite
+			NodeKind::Float(pair.as_str().parse().unwrap())
 		},
 		Rule::boolean => {
-			Node::Bool(
+			NodeKind::Bool(
 				match pair.as_str() {
 					"true" => true,
 					"false" => false,
@@ -46,169 +217,876 @@ fn nodeify(pair: pest::iterators::Pair<Rule>) -> Node {
 				}
 			)
 		},
-		Rule::string_literal => {
-			let slice = pair.as_str();
-			Node::StringLiteral(String::from(&slice[1..slice.len() - 1]))
-		},
+		Rule::string_literal => to_string_node(pair, offset)?,
 		Rule::binary_expr => {
-			let mut values = pair.into_inner();
-			let mut first_val = nodeify(values.next().unwrap());
-
-			while let Some(operator) = values.next() {
-				let lhs = Box::new(first_val);
-				let rhs = Box::new(nodeify(values.next().unwrap()));
-
-				first_val = match operator.as_str() {
-					"+" => Node::Add { lhs, rhs },
-					"-" => Node::Subtract { lhs, rhs },
-					"*" => Node::Multiply { lhs, rhs },
-					"/" => Node::Divide { lhs, rhs },
-					"==" => Node::Equal { lhs, rhs },
-					"!=" => Node::NotEqual { lhs, rhs },
-					"=?" => Node::BoolEqual { lhs, rhs },
-					"!?" => Node::BoolNotEqual { lhs, rhs },
-					"<=" => Node::LessThanOrEqual { lhs, rhs },
-					">=" => Node::GreaterThanOrEqual { lhs, rhs },
-					"<" => Node::LessThan { lhs, rhs },
-					">" => Node::GreaterThan { lhs, rhs },
-					_ => unreachable!("nonexistent operator")
-				};
+			return pratt_parser()
+				.map_primary(|pair| nodeify(pair, offset))
+				.map_infix(|lhs, operator, rhs| {
+					let lhs = Box::new(lhs?);
+					let rhs = Box::new(rhs?);
+					let span = lhs.span.to(rhs.span);
+
+					let kind = match operator.as_rule() {
+						Rule::add => NodeKind::Add { lhs, rhs },
+						Rule::subtract => NodeKind::Subtract { lhs, rhs },
+						Rule::multiply => NodeKind::Multiply { lhs, rhs },
+						Rule::divide => NodeKind::Divide { lhs, rhs },
+						Rule::remainder => NodeKind::Remainder { lhs, rhs },
+						Rule::bit_and => NodeKind::BitAnd { lhs, rhs },
+						Rule::bit_or => NodeKind::BitOr { lhs, rhs },
+						Rule::bit_xor => NodeKind::BitXor { lhs, rhs },
+						Rule::shift_left => NodeKind::ShiftLeft { lhs, rhs },
+						Rule::shift_right => NodeKind::ShiftRight { lhs, rhs },
+						Rule::equal => NodeKind::Equal { lhs, rhs },
+						Rule::not_equal => NodeKind::NotEqual { lhs, rhs },
+						Rule::bool_equal => NodeKind::BoolEqual { lhs, rhs },
+						Rule::bool_not_equal => NodeKind::BoolNotEqual { lhs, rhs },
+						Rule::less_than_or_equal => NodeKind::LessThanOrEqual { lhs, rhs },
+						Rule::greater_than_or_equal => NodeKind::GreaterThanOrEqual { lhs, rhs },
+						Rule::less_than => NodeKind::LessThan { lhs, rhs },
+						Rule::greater_than => NodeKind::GreaterThan { lhs, rhs },
+						Rule::and => NodeKind::And { lhs, rhs },
+						Rule::or => NodeKind::Or { lhs, rhs },
+						_ => unreachable!("nonexistent operator")
+					};
+
+					Ok(Node::new(kind, span))
+				})
+				.map_prefix(|operator, operand| {
+					let operand = Box::new(operand?);
+					let span = to_span(operator.as_span(), offset).to(operand.span);
+
+					let kind = match operator.as_rule() {
+						Rule::negate => NodeKind::Negate(operand),
+						Rule::not => NodeKind::Not(operand),
+						_ => unreachable!("nonexistent operator")
+					};
+
+					Ok(Node::new(kind, span))
+				})
+				.map_postfix(|operand, operator| {
+					let operand = operand?;
+
+					match operator.as_rule() {
+						Rule::cast => {
+							let span = operand.span.to(to_span(operator.as_span(), offset));
+
+							Ok(Node::new(NodeKind::Cast {
+								value: Box::new(operand),
+								target: to_abstract_type(operator.into_inner().next().unwrap())
+							}, span))
+						},
+						_ => to_accessor(operand, operator, offset)
+					}
+				})
+				.parse(pair.into_inner());
+		},
+		Rule::array_literal => {
+			NodeKind::Array(match pair.into_inner().next() {
+				Some(args) => args.into_inner().map(|pair| nodeify(pair, offset)).collect::<Result<_, _>>()?,
+				None => vec![]
+			})
+		},
+		Rule::struct_literal => {
+			let mut inner = pair.into_inner();
+			let name = String::from(inner.next().unwrap().as_str());
+
+			NodeKind::Construct {
+				name,
+				fields: inner.map(|field| {
+					let mut field = field.into_inner();
+					Ok((String::from(field.next().unwrap().as_str()), nodeify(field.next().unwrap(), offset)?))
+				}).collect::<Result<_, ParseError>>()?
+			}
+		},
+		Rule::variant_expr => {
+			let mut inner = pair.into_inner();
+
+			NodeKind::Variant {
+				enum_name: String::from(inner.next().unwrap().as_str()),
+				variant: String::from(inner.next().unwrap().as_str()),
+				values: match inner.next() {
+					Some(args) => args.into_inner().map(|pair| nodeify(pair, offset)).collect::<Result<_, _>>()?,
+					None => vec![]
+				}
 			}
+		},
+		Rule::closure_expr => {
+			let mut inner = pair.into_inner().peekable();
+			let args = match inner.next_if(|pair| pair.as_rule() == Rule::definition_args) {
+				Some(arg_list) => arg_list.into_inner().map(|pair| to_definition(pair, offset)).collect::<Result<_, ParseError>>()?,
+				None => vec![]
+			};
 
-			return first_val;
+			NodeKind::Closure {
+				args,
+				return_type: to_abstract_type(inner.next().unwrap()),
+				body: to_statements(inner.next().unwrap(), offset)?,
+				name: String::new(),
+				captures: vec![]
+			}
 		},
 		Rule::function_expr => {
 			let mut inner = pair.into_inner();
 			let name = inner.next().unwrap();
 			let args = inner.next();
 
-			Node::Function {
+			NodeKind::Function {
 				name: String::from(name.as_str()),
 				args: match args {
 					Some(args) => {
-						args.into_inner().map(|arg| nodeify(arg)).collect()
+						args.into_inner().map(|arg| nodeify(arg, offset)).collect::<Result<_, _>>()?
 					},
 					None => vec![]
 				}
 			}
 		},
 		_ => unreachable!()
+	};
+
+	return Ok(Node::new(kind, span));
+}
+
+// converts a binding_name rule, rejecting reserved keywords
+fn to_name(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<String, ParseError> {
+	let name = pair.as_str();
+
+	match ZincParser::parse(Rule::keyword, name) {
+		Ok(_) => Err(ParseError::ReservedKeyword {
+			keyword: String::from(name),
+			span: to_span(pair.as_span(), offset)
+		}),
+		Err(_) => Ok(String::from(name))
 	}
 }
 
-fn to_abstract_type(type_str: &str) -> AbstractType {
-	match type_str {
-		"i32" => AbstractType::Integer,
-		"bool" => AbstractType::Boolean,
-		"str" => AbstractType::String,
-		"void" => AbstractType::Void,
-		_ => panic!("nonexistent type")
+// decodes the escapes in the contents of a string literal starting at byte `offset` of the source
+fn unescape(contents: &str, offset: usize) -> Result<String, ParseError> {
+	let mut result = String::with_capacity(contents.len());
+	let mut chars = contents.char_indices().peekable();
+
+	while let Some((start, c)) = chars.next() {
+		match c {
+			'\\' => (),
+			// find_interpolations makes sure that a brace outside of a value is doubled
+			'{' | '}' => {
+				chars.next();
+				result.push(c);
+				continue;
+			},
+			'\0' => return Err(ParseError::NulInString {
+				span: Span { start: offset + start, end: offset + start + 1 }
+			}),
+			_ => {
+				result.push(c);
+				continue;
+			}
+		}
+
+		// the grammar guarantees that a backslash is followed by another character
+		let (_, escaped) = chars.next().unwrap();
+		let decoded = match escaped {
+			'n' => Some('\n'),
+			't' => Some('\t'),
+			'"' => Some('"'),
+			'\\' => Some('\\'),
+			'0' => Some('\0'),
+			'u' if chars.next_if(|(_, c)| *c == '{').is_some() => {
+				let mut digits = String::new();
+				while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+					digits.push(digit);
+				}
+
+				match chars.next_if(|(_, c)| *c == '}') {
+					Some(_) if digits.len() <= 6 => u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32),
+					_ => None
+				}
+			},
+			_ => None
+		};
+
+		let end = chars.peek().map_or(contents.len(), |(end, _)| *end);
+
+		match decoded {
+			// strings are NUL terminated in compiled code, so the interpreter would be the only one to see the rest
+			Some('\0') => return Err(ParseError::NulInString {
+				span: Span { start: offset + start, end: offset + end }
+			}),
+			Some(decoded) => result.push(decoded),
+			None => return Err(ParseError::InvalidEscape {
+				escape: String::from(&contents[start..end]),
+				span: Span { start: offset + start, end: offset + end }
+			})
+		}
 	}
+
+	return Ok(result);
 }
 
-fn to_statement(pair: pest::iterators::Pair<Rule>) -> Statement {
-	match pair.as_rule() {
-		Rule::expr | Rule::var_declaration => Statement::Node(nodeify(pair)),
-		Rule::return_statement => Statement::Return(nodeify(pair.into_inner().next().unwrap())),
-		Rule::if_statement => {
-			let mut inner = pair.into_inner();
-			let condition = inner.next().unwrap();
+// finds the values in braces in the contents of a string literal starting at byte `offset` of the source, as the ranges between the braces
+fn find_interpolations(contents: &str, offset: usize) -> Result<Vec<(usize, usize)>, ParseError> {
+	let mut ranges = vec![];
+	let mut chars = contents.char_indices().peekable();
+
+	while let Some((start, c)) = chars.next() {
+		match c {
+			// the braces of a `\u{...}` escape aren't a value, and unescape checks what is between them later
+			'\\' => {
+				if chars.next().map(|(_, escaped)| escaped) == Some('u') && chars.next_if(|(_, c)| *c == '{').is_some() {
+					while chars.next_if(|(_, c)| *c != '}').is_some() {}
+					chars.next();
+				}
+			},
+			'{' | '}' if chars.next_if(|(_, next)| *next == c).is_some() => (),
+			'{' => {
+				let end = match contents[start..].find('}') {
+					Some(length) => start + length,
+					None => return Err(ParseError::UnclosedBrace {
+						span: Span { start: offset + start, end: offset + start + 1 }
+					})
+				};
+
+				while chars.next_if(|(i, _)| *i <= end).is_some() {}
+				ranges.push((start + 1, end));
+			},
+			'}' => return Err(ParseError::UnmatchedBrace {
+				span: Span { start: offset + start, end: offset + start + 1 }
+			}),
+			_ => ()
+		}
+	}
+
+	return Ok(ranges);
+}
 
-			let branch = inner.next().unwrap().into_inner();
-			let branch_statements: Vec<Statement> = branch.map(|pair| {
-				to_statement(pair.into_inner().next().unwrap())
-			}).collect();
+// converts a string_literal rule into the string it stands for, in a place where it can't have values in braces
+fn to_string_literal(pair: pest::iterators::Pair<Rule>, offset: usize, context: &'static str) -> Result<String, ParseError> {
+	let slice = pair.as_str();
+	let contents = &slice[1..slice.len() - 1];
+	let start = offset + pair.as_span().start() + 1;
 
-			let mut else_branch_statements = vec![];
+	if let Some((value_start, value_end)) = find_interpolations(contents, start)?.first() {
+		return Err(ParseError::InterpolationNotAllowed {
+			context,
+			span: Span { start: start + value_start - 1, end: start + value_end + 1 }
+		});
+	}
 
-			if let Some(else_branch) = inner.next() {
-				let else_branch = else_branch.into_inner();
+	unescape(contents, start)
+}
 
-				else_branch.for_each(|pair| {
-					let statement = to_statement(pair.into_inner().next().unwrap());
-					else_branch_statements.push(statement);
-				});
+// converts a string_literal rule in an expression, which is interpolated if it has values in braces
+fn to_string_node(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<NodeKind, ParseError> {
+	let slice = pair.as_str();
+	let contents = &slice[1..slice.len() - 1];
+	let start = offset + pair.as_span().start() + 1;
+
+	let ranges = find_interpolations(contents, start)?;
+	if ranges.is_empty() {
+		return Ok(NodeKind::StringLiteral(unescape(contents, start)?));
+	}
+
+	let text = |from: usize, to: usize| -> Result<Node, ParseError> {
+		let span = Span { start: start + from, end: start + to };
+		Ok(Node::new(NodeKind::StringLiteral(unescape(&contents[from..to], span.start)?), span))
+	};
+
+	let mut parts = vec![];
+	let mut text_start = 0;
+	for (value_start, value_end) in ranges {
+		// the text before the opening brace
+		if text_start < value_start - 1 {
+			parts.push(text(text_start, value_start - 1)?);
+		}
+
+		let value_offset = start + value_start;
+		let value = match ZincParser::parse(Rule::interpolation, &contents[value_start..value_end]) {
+			Ok(mut pairs) => pairs.next().unwrap().into_inner().next().unwrap(),
+			Err(error) => return Err(to_grammar_error(error, value_offset))
+		};
+		parts.push(nodeify(value, value_offset)?);
+
+		text_start = value_end + 1;
+	}
+
+	if text_start < contents.len() {
+		parts.push(text(text_start, contents.len())?);
+	}
+
+	return Ok(NodeKind::Interpolation(parts));
+}
+
+// converts a type_name rule, leaving it to the typecheck pass to find out whether a struct or enum with that name exists
+fn to_abstract_type(pair: pest::iterators::Pair<Rule>) -> AbstractType {
+	let inner = pair.into_inner().next().unwrap();
+
+	match inner.as_rule() {
+		Rule::array_type => AbstractType::Array(Box::new(to_abstract_type(inner.into_inner().next().unwrap()))),
+		Rule::function_type => {
+			let mut args: Vec<AbstractType> = inner.into_inner().map(to_abstract_type).collect();
+			let return_type = args.pop().unwrap();
+
+			AbstractType::Function {
+				args,
+				return_type: Box::new(return_type)
+			}
+		},
+		_ => AbstractType::from_builtin_name(inner.as_str()).unwrap_or_else(|| AbstractType::Named(String::from(inner.as_str())))
+	}
+}
+
+// converts a variable_definition rule
+fn to_definition(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<Definition, ParseError> {
+	let span = to_span(pair.as_span(), offset);
+	let mut inner = pair.into_inner();
+
+	Ok(Definition {
+		name: to_name(inner.next().unwrap(), offset)?,
+		data_type: to_abstract_type(inner.next().unwrap()),
+		span
+	})
+}
+
+// applies an index or field_access rule to the node before it
+fn to_accessor(target: Node, accessor: pest::iterators::Pair<Rule>, offset: usize) -> Result<Node, ParseError> {
+	let span = target.span.to(to_span(accessor.as_span(), offset));
+	let target = Box::new(target);
+
+	let kind = match accessor.as_rule() {
+		Rule::index => NodeKind::Index {
+			array: target,
+			index: Box::new(nodeify(accessor.into_inner().next().unwrap(), offset)?)
+		},
+		Rule::field_access => NodeKind::Field {
+			structure: target,
+			field: String::from(accessor.into_inner().next().unwrap().as_str())
+		},
+		_ => unreachable!("nonexistent accessor")
+	};
+
+	Ok(Node::new(kind, span))
+}
+
+// converts a variant_definition rule
+fn to_variant(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<VariantInfo, ParseError> {
+	let span = to_span(pair.as_span(), offset);
+	let mut inner = pair.into_inner();
+
+	Ok(VariantInfo {
+		name: to_name(inner.next().unwrap(), offset)?,
+		fields: inner.map(to_abstract_type).collect(),
+		span
+	})
+}
+
+fn to_pattern(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<Pattern, ParseError> {
+	let inner = pair.into_inner().next().unwrap();
+
+	match inner.as_rule() {
+		Rule::wildcard => Ok(Pattern::Wildcard),
+		Rule::variant_pattern => {
+			let mut inner = inner.into_inner();
+			let enum_name = String::from(inner.next().unwrap().as_str());
+			let variant = String::from(inner.next().unwrap().as_str());
+
+			Ok(Pattern::Variant {
+				enum_name,
+				variant,
+				bindings: inner.map(|binding| {
+					let binding = binding.into_inner().next().unwrap();
+					match binding.as_rule() {
+						Rule::wildcard => Ok(None),
+						_ => Ok(Some(to_name(binding, offset)?))
+					}
+				}).collect::<Result<_, ParseError>>()?
+			})
+		},
+		Rule::string_literal => {
+			let span = to_span(inner.as_span(), offset);
+			Ok(Pattern::Literal(Node::new(NodeKind::StringLiteral(to_string_literal(inner, offset, "a pattern")?), span)))
+		},
+		_ => Ok(Pattern::Literal(nodeify(inner, offset)?))
+	}
+}
+
+// strips the leading apostrophe from a label rule
+fn to_label(pair: pest::iterators::Pair<Rule>) -> String {
+	return String::from(&pair.as_str()[1..]);
+}
+
+fn to_loop_label(inner: &mut std::iter::Peekable<pest::iterators::Pairs<Rule>>) -> Option<String> {
+	return inner.next_if(|pair| pair.as_rule() == Rule::label).map(to_label);
+}
+
+// converts a lines rule into its statements
+fn to_statements(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<Vec<Statement>, ParseError> {
+	pair.into_inner().map(|line| {
+		to_statement(line.into_inner().next().unwrap(), offset)
+	}).collect()
+}
+
+fn to_statement(pair: pest::iterators::Pair<Rule>, offset: usize) -> Result<Statement, ParseError> {
+	let span = to_span(pair.as_span(), offset);
+
+	let kind = match pair.as_rule() {
+		Rule::expr | Rule::var_declaration => StatementKind::Node(nodeify(pair, offset)?),
+		Rule::assignment => {
+			let mut inner = pair.into_inner();
+			let binding = inner.next().unwrap();
+			let binding_span = to_span(binding.as_span(), offset);
+			let name = to_name(binding, offset)?;
+			let mut accessors: Vec<_> = inner.collect();
+			let value = nodeify(accessors.pop().unwrap(), offset)?;
+
+			match accessors.pop() {
+				Some(last) => {
+					// every accessor but the last one reads the array or struct that is assigned into
+					let mut target = Node::new(NodeKind::Get { name }, binding_span);
+					for accessor in accessors {
+						target = to_accessor(target, accessor, offset)?;
+					}
+
+					match last.as_rule() {
+						Rule::index => StatementKind::AssignIndex {
+							array: target,
+							index: nodeify(last.into_inner().next().unwrap(), offset)?,
+							value
+						},
+						_ => StatementKind::AssignField {
+							structure: target,
+							field: String::from(last.into_inner().next().unwrap().as_str()),
+							value
+						}
+					}
+				},
+				None => StatementKind::Assign {
+					name,
+					value
+				}
 			}
+		},
+		Rule::return_statement => StatementKind::Return(pair.into_inner().next().map(|value| nodeify(value, offset)).transpose()?),
+		Rule::if_statement => {
+			let mut inner = pair.into_inner();
+			let condition = inner.next().unwrap();
+			let branch_statements = to_statements(inner.next().unwrap(), offset)?;
 
-			Statement::If {
-				condition: nodeify(condition),
+			let else_branch_statements = match inner.next() {
+				Some(else_branch) => to_statements(else_branch, offset)?,
+				None => vec![]
+			};
+
+			StatementKind::If {
+				condition: nodeify(condition, offset)?,
 				branch: branch_statements,
 				else_branch: else_branch_statements
 			}
 		},
 		Rule::while_loop => {
-			let mut inner = pair.into_inner();
-			let condition = nodeify(inner.next().unwrap());
-			let loop_statements = inner.next().unwrap().into_inner().map(|pair| {
-				to_statement(pair.into_inner().next().unwrap())
-			}).collect();
+			let mut inner = pair.into_inner().peekable();
+			let label = to_loop_label(&mut inner);
+			let condition = nodeify(inner.next().unwrap(), offset)?;
+			let loop_statements = to_statements(inner.next().unwrap(), offset)?;
 
-			Statement::While {
+			StatementKind::While {
+				label,
 				condition,
 				loop_statements
 			}
 		},
 		Rule::infinite_loop => {
-			let loop_statements = pair.into_inner().next().unwrap();
-			Statement::InfiniteLoop(loop_statements.into_inner().map(|pair| {
-				to_statement(pair.into_inner().next().unwrap())
-			}).collect())
+			let mut inner = pair.into_inner().peekable();
+			let label = to_loop_label(&mut inner);
+			let loop_statements = to_statements(inner.next().unwrap(), offset)?;
+
+			StatementKind::InfiniteLoop {
+				label,
+				loop_statements
+			}
 		},
+		Rule::for_loop => {
+			let mut inner = pair.into_inner().peekable();
+			let label = to_loop_label(&mut inner);
+			let variable = to_name(inner.next().unwrap(), offset)?;
+			let start = nodeify(inner.next().unwrap(), offset)?;
+			let inclusive = inner.next().unwrap().as_str() == "..=";
+			let end = nodeify(inner.next().unwrap(), offset)?;
+
+			let step = match inner.next_if(|pair| pair.as_rule() == Rule::step_size) {
+				Some(step) => {
+					let span = to_span(step.as_span(), offset);
+					match step.as_str().parse() {
+						Ok(0) => return Err(ParseError::ZeroStep { span }),
+						Ok(val) => val,
+						Err(_) => return Err(ParseError::IntegerOutOfBounds {
+							literal: String::from(step.as_str()),
+							int_type: IntegerType::I32,
+							span
+						})
+					}
+				},
+				None => 1
+			};
+
+			StatementKind::For {
+				label,
+				variable,
+				start,
+				end,
+				inclusive,
+				step,
+				loop_statements: to_statements(inner.next().unwrap(), offset)?
+			}
+		},
+		Rule::match_statement => {
+			let mut inner = pair.into_inner();
+			let value = nodeify(inner.next().unwrap(), offset)?;
+
+			StatementKind::Match {
+				value,
+				arms: inner.map(|arm| {
+					let span = to_span(arm.as_span(), offset);
+					let mut inner = arm.into_inner();
+
+					Ok(MatchArm {
+						pattern: to_pattern(inner.next().unwrap(), offset)?,
+						statements: to_statements(inner.next().unwrap(), offset)?,
+						span
+					})
+				}).collect::<Result<_, ParseError>>()?
+			}
+		},
+		Rule::break_statement => StatementKind::Break(pair.into_inner().next().map(to_label)),
+		Rule::continue_statement => StatementKind::Continue(pair.into_inner().next().map(to_label)),
 		_ => unreachable!()
-	}
+	};
+
+	return Ok(Statement {
+		kind,
+		span
+	});
+}
+
+pub fn parse(code: &str) -> Result<FileDescription, ParseError> {
+	parse_at(code, 0)
 }
 
-pub fn parse(code: &str) -> Result<FileDescription, pest::error::Error<Rule>> {
-	let file = ZincParser::parse(Rule::file, &code)?.next().unwrap();
+/// Parses a file whose source starts at byte `offset` of the program, so that its spans don't overlap with other files'.
+pub fn parse_at(code: &str, offset: usize) -> Result<FileDescription, ParseError> {
+	let file = match ZincParser::parse(Rule::file, code) {
+		Ok(mut pairs) => pairs.next().unwrap(),
+		Err(error) => return Err(to_grammar_error(error, offset))
+	};
 
 	let mut statements: Vec<Statement> = vec![];
 	let mut functions: HashMap<String, FunctionInfo> = HashMap::new();
+	let mut types = TypeMap::new();
+	let mut globals: Vec<GlobalInfo> = vec![];
+	let mut imports: Vec<Import> = vec![];
 
-	file.into_inner().filter(|pair| {
-		pair.as_rule() != Rule::EOI
-	}).for_each(|pair| {
+	for pair in file.into_inner() {
 		match pair.as_rule() {
 			Rule::line => {
 				let inner_line = pair.into_inner().next().unwrap();
 
-				statements.push(to_statement(inner_line));
+				statements.push(to_statement(inner_line, offset)?);
+			},
+			Rule::import_statement => {
+				let span = to_span(pair.as_span(), offset);
+
+				imports.push(Import {
+					path: to_string_literal(pair.into_inner().next().unwrap(), offset, "an import")?,
+					span
+				});
+			},
+			Rule::global_declaration => {
+				let mut inner = pair.into_inner();
+				let constant = inner.next().unwrap().as_str() == "const";
+				let definition = to_definition(inner.next().unwrap(), offset)?;
+
+				add_global(&mut globals, GlobalInfo {
+					name: definition.name,
+					data_type: definition.data_type,
+					value: nodeify(inner.next().unwrap(), offset)?,
+					constant,
+					span: definition.span
+				})?;
 			},
 			Rule::func_declaration => {
 				let mut function = pair.into_inner();
 				let mut signature = function.next().unwrap().into_inner();
 				let return_type = function.next().unwrap();
-				let lines: Vec<Statement> = function.next().unwrap().into_inner().map(|line| {
-					to_statement(line.into_inner().next().unwrap())
-				}).collect();
+				let lines = to_statements(function.next().unwrap(), offset)?;
 
 				let function_name = signature.next().unwrap();
+				let span = to_span(function_name.as_span(), offset);
+				let function_name = to_name(function_name, offset)?;
 
 				let args: Vec<Definition> = match signature.next() {
-					Some(arg_list) => {
-						arg_list.into_inner()
-						.map(|arg| arg.into_inner())
-						.map(|mut arg| {
-							Definition {
-								name: String::from(arg.next().unwrap().as_str()),
-								data_type: to_abstract_type(arg.next().unwrap().as_str())
-							}
-						}).collect()
-					},
+					Some(arg_list) => arg_list.into_inner().map(|pair| to_definition(pair, offset)).collect::<Result<_, ParseError>>()?,
 					None => vec![]
 				};
 
-				functions.insert(String::from(function_name.as_str()), FunctionInfo {
+				add_function(&mut functions, function_name, FunctionInfo {
 					body: lines,
 					args,
-					return_type: to_abstract_type(return_type.as_str())
-				});
-			}
+					return_type: to_abstract_type(return_type),
+					span,
+					captures: None
+				})?;
+			},
+			Rule::struct_declaration => {
+				let mut inner = pair.into_inner();
+				let struct_name = inner.next().unwrap();
+				let span = to_span(struct_name.as_span(), offset);
+
+				add_type(&mut types, to_name(struct_name, offset)?, TypeInfo::Struct(StructInfo {
+					fields: inner.map(|pair| to_definition(pair, offset)).collect::<Result<_, ParseError>>()?,
+					span
+				}))?;
+			},
+			Rule::enum_declaration => {
+				let mut inner = pair.into_inner();
+				let enum_name = inner.next().unwrap();
+				let span = to_span(enum_name.as_span(), offset);
+
+				add_type(&mut types, to_name(enum_name, offset)?, TypeInfo::Enum(EnumInfo {
+					variants: inner.map(|pair| to_variant(pair, offset)).collect::<Result<_, ParseError>>()?,
+					span
+				}))?;
+			},
+			Rule::EOI => (),
 			_ => unreachable!()
 		}
-	});
+	}
 
 	Ok(FileDescription {
 		statements,
-		functions
+		functions,
+		types,
+		globals,
+		imports
 	})
 }
+
+fn add_function(functions: &mut HashMap<String, FunctionInfo>, name: String, info: FunctionInfo) -> Result<(), ParseError> {
+	if functions.contains_key(&name) {
+		return Err(ParseError::DuplicateFunction {
+			name,
+			span: info.span
+		});
+	}
+
+	functions.insert(name, info);
+	return Ok(());
+}
+
+fn add_type(types: &mut TypeMap, name: String, info: TypeInfo) -> Result<(), ParseError> {
+	if types.contains_key(&name) {
+		return Err(ParseError::DuplicateType {
+			name,
+			span: info.span()
+		});
+	}
+
+	types.insert(name, info);
+	return Ok(());
+}
+
+fn add_global(globals: &mut Vec<GlobalInfo>, info: GlobalInfo) -> Result<(), ParseError> {
+	if globals.iter().any(|global| global.name == info.name) {
+		return Err(ParseError::DuplicateGlobal {
+			name: info.name,
+			span: info.span
+		});
+	}
+
+	globals.push(info);
+	return Ok(());
+}
+
+/// Adds the functions, types and globals of an imported file to the program.
+pub fn merge(program: &mut FileDescription, imported: FileDescription) -> Result<(), ParseError> {
+	if let Some(statement) = imported.statements.first() {
+		return Err(ParseError::StatementInImport {
+			span: statement.span
+		});
+	}
+
+	// in source order, so that the first duplicate is the one that gets reported
+	let mut functions: Vec<_> = imported.functions.into_iter().collect();
+	functions.sort_by_key(|(_, info)| info.span.start);
+	for (name, info) in functions {
+		add_function(&mut program.functions, name, info)?;
+	}
+
+	let mut types: Vec<_> = imported.types.into_iter().collect();
+	types.sort_by_key(|(_, info)| info.span().start);
+	for (name, info) in types {
+		add_type(&mut program.types, name, info)?;
+	}
+
+	for info in imported.globals {
+		add_global(&mut program.globals, info)?;
+	}
+
+	return Ok(());
+}
+
+#[test]
+fn test_parse_errors() {
+	let error = parse("let x: i32 = 2147483648i32;").unwrap_err();
+	assert_eq!(error.to_string(), "integer literal `2147483648i32` does not fit in `i32`");
+	assert_eq!(error.span(), Span { start: 13, end: 26 });
+
+	// the typecheck pass checks the range of unsuffixed literals, once it knows their type
+	assert_eq!(parse("let x: u64 = 18446744073709551615;").unwrap().statements.len(), 1);
+
+	let error = parse("let x: i32 = ;").unwrap_err();
+	assert!(matches!(error, ParseError::Grammar { .. }));
+	assert_eq!(error.span().start, 13);
+
+	let error = parse("for i in 0..10 step 0 {}").unwrap_err();
+	assert_eq!(error.to_string(), "for loop step cannot be zero");
+	assert_eq!(error.span(), Span { start: 20, end: 21 });
+
+	let error = parse(r#"print_str("a\q");"#).unwrap_err();
+	assert_eq!(error.to_string(), "invalid escape `\\q` in string literal");
+	assert_eq!(error.span(), Span { start: 12, end: 14 });
+
+	let error = parse(r#"print_str("\u{110000}");"#).unwrap_err();
+	assert_eq!(error.to_string(), "invalid escape `\\u{110000}` in string literal");
+
+	let error = parse(r#"print_int(str_len("a\0b"));"#).unwrap_err();
+	assert_eq!(error.to_string(), "string literals cannot contain a NUL character, compiled programs would end the string there");
+	assert_eq!(error.span(), Span { start: 20, end: 22 });
+
+	let error = parse("print_str(\"a\\u{0}\0b\");").unwrap_err();
+	assert_eq!(error.span(), Span { start: 12, end: 17 });
+
+	let error = parse("print_str(\"a\0b\");").unwrap_err();
+	assert!(matches!(error, ParseError::NulInString { .. }));
+	assert_eq!(error.span(), Span { start: 12, end: 13 });
+
+	let error = parse("let while: i32 = 5;").unwrap_err();
+	assert_eq!(error.to_string(), "`while` is a reserved keyword and cannot be used as a name");
+	assert_eq!(error.span(), Span { start: 4, end: 9 });
+
+	let error = parse("fn true() -> void {}").unwrap_err();
+	assert_eq!(error.to_string(), "`true` is a reserved keyword and cannot be used as a name");
+
+	let error = parse("let x: u8 = 256u8;").unwrap_err();
+	assert_eq!(error.to_string(), "integer literal `256u8` does not fit in `u8`");
+
+	let error = parse("let x: u64 = -1u64;").unwrap_err();
+	assert_eq!(error.to_string(), "integer literal `-1u64` does not fit in `u64`");
+
+	let error = parse("struct in { x: i32 }").unwrap_err();
+	assert_eq!(error.to_string(), "`in` is a reserved keyword and cannot be used as a name");
+
+	assert_eq!(parse("let x: i32 = -2147483648;").unwrap().statements.len(), 1);
+	assert_eq!(parse("let x: u64 = 18446744073709551615u64; let y: i8 = -128i8;").unwrap().statements.len(), 2);
+	assert_eq!(parse("let a: [[i32]] = [[1, 2], []]; a[0][1] = -a[1 + 1][0] as i32;").unwrap().statements.len(), 2);
+	assert_eq!(parse("struct P { x: [P], } let p: P = P { x: [], }; p.x[0].x = p.x; for i in 0..p.x {}").unwrap().statements.len(), 3);
+	assert_eq!(parse("enum E { A, B(i32, [E]), } let e: E = E::B(1, [E::A]); match (e) { E::B(x, _) => { e = E::A; } _ => {} }").unwrap().statements.len(), 2);
+	assert_eq!(parse("match (5) { -1 => {} 2u8 => {} \"a\" => {} true => {} }").unwrap().statements.len(), 1);
+
+	let error = parse("match (e) { E::B(x, match) => {} }").unwrap_err();
+	assert_eq!(error.to_string(), "`match` is a reserved keyword and cannot be used as a name");
+
+	assert_eq!(parse("fn apply(f: fn(i32, [bool]) -> fn() -> void, g: fn ( ) -> [i32]) -> void { f(1, []); }").unwrap().functions.len(), 1);
+
+	assert_eq!(parse("let k: i32 = 1; let f: fn(i32) -> i32 = |x: i32| -> i32 { return x + k; }; let g: fn() -> bool = || -> bool { return true || false; };").unwrap().statements.len(), 3);
+
+	assert_eq!(parse("let sha256: i32 = 1; let iffy: bool = true; let trueish: i32 = sha256;").unwrap().statements.len(), 3);
+
+	let error = parse("fn f() -> void {} struct S { x: i32 } fn f() -> i32 { return 1; }").unwrap_err();
+	assert_eq!(error.to_string(), "function `f` is defined more than once");
+	assert_eq!(error.span(), Span { start: 41, end: 42 });
+
+	let error = parse("enum S { A } struct S { x: i32 }").unwrap_err();
+	assert_eq!(error.to_string(), "type `S` is defined more than once");
+
+	let error = parse("const A: i32 = 1; static B: bool = true; static A: str = \"a\";").unwrap_err();
+	assert_eq!(error.to_string(), "global `A` is defined more than once");
+	assert_eq!(error.span(), Span { start: 48, end: 54 });
+
+	let file_description = parse("let n = n + 1; let m: u8 = 2u8;").unwrap();
+	let types: Vec<_> = file_description.statements.iter().map(|statement| match &statement.kind {
+		StatementKind::Node(Node { kind: NodeKind::Set { var_type, .. }, .. }) => var_type.clone(),
+		_ => unreachable!()
+	}).collect();
+	assert_eq!(types, [None, Some(AbstractType::Integer(IntegerType::U8))]);
+
+	let error = parse("let static: i32 = 1;").unwrap_err();
+	assert_eq!(error.to_string(), "`static` is a reserved keyword and cannot be used as a name");
+
+	let error = parse("let import: i32 = 1;").unwrap_err();
+	assert_eq!(error.to_string(), "`import` is a reserved keyword and cannot be used as a name");
+}
+
+#[test]
+fn test_imports() {
+	let mut program = parse(r#"import "lib/util.zn"; import "../a\"b.zn"; util(1);"#).unwrap();
+	let paths: Vec<&str> = program.imports.iter().map(|import| import.path.as_str()).collect();
+	assert_eq!(paths, ["lib/util.zn", "../a\"b.zn"]);
+	assert_eq!(program.imports[0].span, Span { start: 0, end: 21 });
+
+	// the spans of an imported file start at its offset
+	let imported = parse_at("fn util(x: i32) -> void {}\nstruct Util { x: i32 }", 100).unwrap();
+	assert_eq!(imported.functions["util"].span, Span { start: 103, end: 107 });
+	merge(&mut program, imported).unwrap();
+	assert!(program.functions.contains_key("util") && program.types.contains_key("Util"));
+
+	let error = parse_at("fn util(x: i32) -> void {}", 200).and_then(|imported| merge(&mut program, imported)).unwrap_err();
+	assert_eq!(error.to_string(), "function `util` is defined more than once");
+	assert_eq!(error.span(), Span { start: 203, end: 207 });
+
+	let error = parse_at("fn other() -> void {} util(2);", 300).and_then(|imported| merge(&mut program, imported)).unwrap_err();
+	assert_eq!(error.to_string(), "an imported file can only declare functions, types and globals");
+	assert_eq!(error.span(), Span { start: 322, end: 329 });
+
+	let error = parse_at("let x: i32 = ;", 400).unwrap_err();
+	assert_eq!(error.span().start, 413);
+}
+
+#[test]
+fn test_interpolation() {
+	let value = |code: &str| match parse(code).unwrap().statements.remove(0).kind {
+		StatementKind::Node(Node { kind: NodeKind::Set { value, .. }, .. }) => value.kind,
+		_ => unreachable!()
+	};
+
+	let parts = match value(r#"let s = "n = {n + 1}, {{n}}";"#) {
+		NodeKind::Interpolation(parts) => parts,
+		_ => unreachable!()
+	};
+	assert_eq!(parts.len(), 3);
+	assert!(matches!(&parts[0].kind, NodeKind::StringLiteral(text) if text == "n = "));
+	assert!(matches!(parts[1].kind, NodeKind::Add { .. }));
+	assert_eq!(parts[1].span, Span { start: 14, end: 19 });
+	assert!(matches!(&parts[2].kind, NodeKind::StringLiteral(text) if text == ", {n}"));
+
+	assert!(matches!(value(r#"let s = "{{}}";"#), NodeKind::StringLiteral(text) if text == "{}"));
+
+	let error = parse(r#"print_str("a {b");"#).unwrap_err();
+	assert_eq!(error.to_string(), "unclosed `{` in string literal, use `{{` for a brace on its own");
+	assert_eq!(error.span(), Span { start: 13, end: 14 });
+
+	let error = parse(r#"print_str("a } b");"#).unwrap_err();
+	assert_eq!(error.to_string(), "unmatched `}` in string literal, use `}}` for a brace on its own");
+	assert_eq!(error.span(), Span { start: 13, end: 14 });
+
+	let error = parse(r#"print_str("{1 +}");"#).unwrap_err();
+	assert!(matches!(error, ParseError::Grammar { .. }));
+	assert_eq!(error.span().start, 15);
+
+	let error = parse(r#"print_str("\u{zz} {1}");"#).unwrap_err();
+	assert_eq!(error.to_string(), "invalid escape `\\u{` in string literal");
+
+	let error = parse(r#"match ("a") { "{x}" => {} }"#).unwrap_err();
+	assert_eq!(error.to_string(), "values cannot be put in the string of a pattern");
+
+	let error = parse(r#"import "{x}.zn";"#).unwrap_err();
+	assert_eq!(error.to_string(), "values cannot be put in the string of an import");
+}
@@ -22,6 +22,27 @@ pub fn populate_stdlib<M: Module>(fn_map: &mut FuncMap, object_module: &mut M) {
 		add_function(fn_map, object_module, "print_int", print_int_sig);
 	}
 
+	// narrow integers are extended to a full register, which C expects of its callers
+	for (name, param) in [
+		("print_i8", AbiParam::new(I8).sext()),
+		("print_i16", AbiParam::new(I16).sext()),
+		("print_i64", AbiParam::new(I64)),
+		("print_u8", AbiParam::new(I8).uext()),
+		("print_u16", AbiParam::new(I16).uext()),
+		("print_u32", AbiParam::new(I32)),
+		("print_u64", AbiParam::new(I64))
+	] {
+		let mut print_sig = object_module.make_signature();
+		print_sig.params.push(param);
+		add_function(fn_map, object_module, name, print_sig);
+	}
+
+	{
+		let mut print_float_sig = object_module.make_signature();
+		print_float_sig.params.push(AbiParam::new(F64));
+		add_function(fn_map, object_module, "print_float", print_float_sig);
+	}
+
 	{
 		let mut print_bool_sig = object_module.make_signature();
 		print_bool_sig.params.push(AbiParam::new(B1));
@@ -57,6 +78,19 @@ pub fn populate_stdlib<M: Module>(fn_map: &mut FuncMap, object_module: &mut M) {
 		add_function(fn_map, object_module, "str_concat", str_concat_sig);
 	}
 
+	// formatting for interpolated strings, which widen integers to 64 bits first
+	for (name, param) in [
+		("i64_to_str", AbiParam::new(I64)),
+		("u64_to_str", AbiParam::new(I64)),
+		("float_to_str", AbiParam::new(F64)),
+		("bool_to_str", AbiParam::new(B1))
+	] {
+		let mut to_str_sig = object_module.make_signature();
+		to_str_sig.params.push(param);
+		to_str_sig.returns.push(AbiParam::new(pointer_type));
+		add_function(fn_map, object_module, name, to_str_sig);
+	}
+
 	{
 		let mut assert_int_eq_sig = object_module.make_signature();
 		assert_int_eq_sig.params.push(AbiParam::new(I32));
@@ -82,4 +116,41 @@ pub fn populate_stdlib<M: Module>(fn_map: &mut FuncMap, object_module: &mut M) {
 		let panic_sig = object_module.make_signature();
 		add_function(fn_map, object_module, "panic", panic_sig);
 	}
+
+	// runtime support for arrays and structs, which user code reaches through literals, indexing, len and push
+	{
+		let mut array_new_sig = object_module.make_signature();
+		array_new_sig.params.push(AbiParam::new(I32));
+		array_new_sig.returns.push(AbiParam::new(pointer_type));
+		add_function(fn_map, object_module, "zinc_array_new", array_new_sig);
+	}
+
+	{
+		let mut array_slot_sig = object_module.make_signature();
+		array_slot_sig.params.push(AbiParam::new(pointer_type));
+		array_slot_sig.params.push(AbiParam::new(I32));
+		array_slot_sig.returns.push(AbiParam::new(pointer_type));
+		add_function(fn_map, object_module, "zinc_array_slot", array_slot_sig);
+	}
+
+	{
+		let mut array_len_sig = object_module.make_signature();
+		array_len_sig.params.push(AbiParam::new(pointer_type));
+		array_len_sig.returns.push(AbiParam::new(I32));
+		add_function(fn_map, object_module, "zinc_array_len", array_len_sig);
+	}
+
+	{
+		let mut array_push_sig = object_module.make_signature();
+		array_push_sig.params.push(AbiParam::new(pointer_type));
+		array_push_sig.returns.push(AbiParam::new(pointer_type));
+		add_function(fn_map, object_module, "zinc_array_push", array_push_sig);
+	}
+
+	{
+		let mut struct_new_sig = object_module.make_signature();
+		struct_new_sig.params.push(AbiParam::new(I32));
+		struct_new_sig.returns.push(AbiParam::new(pointer_type));
+		add_function(fn_map, object_module, "zinc_struct_new", struct_new_sig);
+	}
 }
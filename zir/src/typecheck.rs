@@ -0,0 +1,1846 @@
+use crate::node::{FileDescription, FunctionInfo, GlobalInfo, Definition, Statement, StatementKind, Node, NodeKind, AbstractType, IntegerType, StructInfo, EnumInfo, TypeInfo, TypeMap, Pattern, MatchArm, Span};
+use crate::constant::{self, Constant};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug)]
+pub struct TypeError {
+	pub kind: TypeErrorKind,
+	pub span: Span
+}
+
+#[derive(Debug)]
+pub enum TypeErrorKind {
+	MismatchedTypes {
+		context: &'static str,
+		expected: AbstractType,
+		found: AbstractType
+	},
+	InvalidOperands {
+		operator: &'static str,
+		lhs: AbstractType,
+		rhs: AbstractType
+	},
+	InvalidOperand {
+		operator: &'static str,
+		operand: AbstractType
+	},
+	IncorrectArgumentNumber {
+		function: String,
+		expected: usize,
+		found: usize
+	},
+	VoidVariable(String),
+	UndefinedVariable(String),
+	UndefinedFunction(String),
+	RedefinedStdFunction(String),
+	ReservedFunctionName(String),
+	InvalidCast {
+		from: AbstractType,
+		to: AbstractType
+	},
+	OutsideOfLoop(&'static str),
+	UndefinedLabel(String),
+	ExpectedArray {
+		function: String,
+		found: AbstractType
+	},
+	EmptyArray,
+	VoidElement,
+	UnknownType(String),
+	RedefinedBuiltinType(String),
+	VoidField(String),
+	DuplicateField(String),
+	MissingField {
+		structure: String,
+		field: String
+	},
+	NoField {
+		found: AbstractType,
+		field: String
+	},
+	NotAStruct(String),
+	NotAnEnum(String),
+	DuplicateVariant(String),
+	VoidPayload(String),
+	UnknownVariant {
+		enum_name: String,
+		variant: String
+	},
+	IncorrectPayloadNumber {
+		variant: String,
+		expected: usize,
+		found: usize
+	},
+	NonExhaustiveMatch(Vec<String>),
+	UnreachablePattern,
+	VoidParameter,
+	NotAFunction {
+		name: String,
+		found: AbstractType
+	},
+	AssignToCapture(String),
+	MissingReturnValue(AbstractType),
+	MissingReturn(AbstractType),
+	NotConstant,
+	InvalidConstant(&'static str),
+	AssignToConst(String),
+	CallInStatic(String),
+	InvalidInterpolation(AbstractType),
+	LiteralOutOfRange {
+		value: i128,
+		int_type: IntegerType
+	}
+}
+
+impl std::fmt::Display for TypeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.kind)
+	}
+}
+
+impl std::fmt::Display for TypeErrorKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			TypeErrorKind::MismatchedTypes { context, expected, found } => {
+				write!(f, "mismatched types in {}, expected `{}` but found `{}`", context, expected, found)
+			},
+			TypeErrorKind::InvalidOperands { operator, lhs, rhs } => {
+				write!(f, "operator `{}` cannot be applied to `{}` and `{}`", operator, lhs, rhs)
+			},
+			TypeErrorKind::InvalidOperand { operator, operand } => {
+				write!(f, "operator `{}` cannot be applied to `{}`", operator, operand)
+			},
+			TypeErrorKind::IncorrectArgumentNumber { function, expected, found } => {
+				write!(f, "function `{}` takes {} argument(s) but {} were supplied", function, expected, found)
+			},
+			TypeErrorKind::VoidVariable(name) => write!(f, "variable `{}` cannot be void", name),
+			TypeErrorKind::UndefinedVariable(name) => write!(f, "undeclared variable `{}`", name),
+			TypeErrorKind::UndefinedFunction(name) => write!(f, "undefined function `{}`", name),
+			TypeErrorKind::RedefinedStdFunction(name) => write!(f, "function `{}` is already defined by the standard library", name),
+			TypeErrorKind::ReservedFunctionName(name) => write!(f, "function `{}` cannot be defined, names starting with `zinc_` are reserved for the runtime", name),
+			TypeErrorKind::InvalidCast { from, to } => write!(f, "cannot cast `{}` as `{}`", from, to),
+			TypeErrorKind::OutsideOfLoop(keyword) => write!(f, "`{}` outside of a loop", keyword),
+			TypeErrorKind::UndefinedLabel(label) => write!(f, "undeclared label `'{}`", label),
+			TypeErrorKind::ExpectedArray { function, found } => write!(f, "function `{}` expects an array but found `{}`", function, found),
+			TypeErrorKind::EmptyArray => write!(f, "cannot infer the element type of an empty array"),
+			TypeErrorKind::VoidElement => write!(f, "array elements cannot be void"),
+			TypeErrorKind::UnknownType(name) => write!(f, "unknown type `{}`", name),
+			TypeErrorKind::RedefinedBuiltinType(name) => write!(f, "type `{}` is already built into the language", name),
+			TypeErrorKind::VoidField(name) => write!(f, "field `{}` cannot be void", name),
+			TypeErrorKind::DuplicateField(name) => write!(f, "field `{}` is listed more than once", name),
+			TypeErrorKind::MissingField { structure, field } => write!(f, "missing field `{}` in constructor of `{}`", field, structure),
+			TypeErrorKind::NoField { found, field } => write!(f, "no field `{}` on type `{}`", field, found),
+			TypeErrorKind::NotAStruct(name) => write!(f, "`{}` is not a struct", name),
+			TypeErrorKind::NotAnEnum(name) => write!(f, "`{}` is not an enum", name),
+			TypeErrorKind::DuplicateVariant(name) => write!(f, "variant `{}` is listed more than once", name),
+			TypeErrorKind::VoidPayload(name) => write!(f, "variant `{}` cannot carry void", name),
+			TypeErrorKind::UnknownVariant { enum_name, variant } => write!(f, "no variant `{}` in enum `{}`", variant, enum_name),
+			TypeErrorKind::IncorrectPayloadNumber { variant, expected, found } => {
+				write!(f, "variant `{}` carries {} value(s) but {} were supplied", variant, expected, found)
+			},
+			TypeErrorKind::NonExhaustiveMatch(missing) => {
+				let missing: Vec<String> = missing.iter().map(|pattern| format!("`{}`", pattern)).collect();
+				write!(f, "match is not exhaustive, missing {}", missing.join(", "))
+			},
+			TypeErrorKind::UnreachablePattern => write!(f, "unreachable pattern"),
+			TypeErrorKind::VoidParameter => write!(f, "function parameters cannot be void"),
+			TypeErrorKind::NotAFunction { name, found } => write!(f, "variable `{}` of type `{}` cannot be called", name, found),
+			TypeErrorKind::AssignToCapture(name) => write!(f, "cannot assign to `{}`, which is captured by a closure", name),
+			TypeErrorKind::MissingReturnValue(expected) => write!(f, "`return` without a value in a function that returns `{}`", expected),
+			TypeErrorKind::MissingReturn(expected) => write!(f, "function can reach its end without returning a `{}`", expected),
+			TypeErrorKind::NotConstant => write!(f, "a const can only be made of literals, operators, casts and other consts"),
+			TypeErrorKind::InvalidConstant(message) => write!(f, "cannot evaluate const, {}", message),
+			TypeErrorKind::AssignToConst(name) => write!(f, "cannot assign to const `{}`", name),
+			TypeErrorKind::CallInStatic(name) => write!(f, "a static cannot be initialized by calling `{}`, which could use statics that aren't initialized yet", name),
+			TypeErrorKind::InvalidInterpolation(found) => write!(f, "only integers, floats, bools and strings can be put in a string, found `{}`", found),
+			TypeErrorKind::LiteralOutOfRange { value, int_type } => write!(f, "integer literal `{}` does not fit in `{}`", value, int_type)
+		}
+	}
+}
+
+type Signature = (Vec<AbstractType>, AbstractType);
+
+// keep in sync with stdlib::populate_stdlib and interpreter::try_std_function
+fn std_signature(name: &str) -> Option<Signature> {
+	use AbstractType::*;
+	use IntegerType::*;
+
+	let signature = match name {
+		"print_int" => (vec![Integer(I32)], Void),
+		"print_i8" => (vec![Integer(I8)], Void),
+		"print_i16" => (vec![Integer(I16)], Void),
+		"print_i64" => (vec![Integer(I64)], Void),
+		"print_u8" => (vec![Integer(U8)], Void),
+		"print_u16" => (vec![Integer(U16)], Void),
+		"print_u32" => (vec![Integer(U32)], Void),
+		"print_u64" => (vec![Integer(U64)], Void),
+		"print_float" => (vec![Float], Void),
+		"print_bool" => (vec![Boolean], Void),
+		"print_str" => (vec![String], Void),
+		"str_eq" => (vec![String, String], Boolean),
+		"str_len" => (vec![String], Integer(I32)),
+		"str_concat" => (vec![String, String], String),
+		"i64_to_str" => (vec![Integer(I64)], String),
+		"u64_to_str" => (vec![Integer(U64)], String),
+		"float_to_str" => (vec![Float], String),
+		"bool_to_str" => (vec![Boolean], String),
+		"assert_int_eq" => (vec![Integer(I32), Integer(I32)], Void),
+		"assert_bool_eq" => (vec![Boolean, Boolean], Void),
+		"assert_str_eq" => (vec![String, String], Void),
+		"panic" => (vec![], Void),
+		_ => return None
+	};
+
+	return Some(signature);
+}
+
+// len and push work on arrays of any element type, so they have no fixed signature
+fn is_array_function(name: &str) -> bool {
+	matches!(name, "len" | "push")
+}
+
+// finds a struct or enum type that isn't declared, or an array or parameter of void, anywhere in a type
+fn unsuffixed(node: &Node) -> bool {
+	return matches!(node.kind, NodeKind::Int { suffixed: false, .. });
+}
+
+fn invalid_type(data_type: &AbstractType, types: &TypeMap) -> Option<TypeErrorKind> {
+	match data_type {
+		AbstractType::Array(element_type) if **element_type == AbstractType::Void => Some(TypeErrorKind::VoidElement),
+		AbstractType::Array(element_type) => invalid_type(element_type, types),
+		AbstractType::Named(name) if !types.contains_key(name) => Some(TypeErrorKind::UnknownType(name.to_string())),
+		AbstractType::Function { args, return_type } => args.iter().find_map(|arg| match arg {
+			AbstractType::Void => Some(TypeErrorKind::VoidParameter),
+			arg => invalid_type(arg, types)
+		}).or_else(|| invalid_type(return_type, types)),
+		_ => None
+	}
+}
+
+fn check_declaration(name: &str, info: &TypeInfo, types: &TypeMap, errors: &mut Vec<TypeError>) {
+	if AbstractType::from_builtin_name(name).is_some() {
+		errors.push(TypeError {
+			kind: TypeErrorKind::RedefinedBuiltinType(name.to_string()),
+			span: info.span()
+		});
+	}
+
+	match info {
+		TypeInfo::Struct(info) => check_struct(info, types, errors),
+		TypeInfo::Enum(info) => check_enum(info, types, errors)
+	}
+}
+
+fn check_struct(info: &StructInfo, types: &TypeMap, errors: &mut Vec<TypeError>) {
+	let mut seen = HashSet::new();
+	for field in info.fields.iter() {
+		let kind = match &field.data_type {
+			_ if !seen.insert(&field.name) => Some(TypeErrorKind::DuplicateField(field.name.clone())),
+			AbstractType::Void => Some(TypeErrorKind::VoidField(field.name.clone())),
+			data_type => invalid_type(data_type, types)
+		};
+
+		if let Some(kind) = kind {
+			errors.push(TypeError {
+				kind,
+				span: field.span
+			});
+		}
+	}
+}
+
+fn check_enum(info: &EnumInfo, types: &TypeMap, errors: &mut Vec<TypeError>) {
+	let mut seen = HashSet::new();
+	for variant in info.variants.iter() {
+		let mut kinds = vec![];
+		if !seen.insert(&variant.name) {
+			kinds.push(TypeErrorKind::DuplicateVariant(variant.name.clone()));
+		}
+
+		for data_type in variant.fields.iter() {
+			kinds.extend(match data_type {
+				AbstractType::Void => Some(TypeErrorKind::VoidPayload(variant.name.clone())),
+				data_type => invalid_type(data_type, types)
+			});
+		}
+
+		for kind in kinds {
+			errors.push(TypeError {
+				kind,
+				span: variant.span
+			});
+		}
+	}
+}
+
+// whether the statements always end in a return, which is worked out without looking at any conditions
+fn always_returns(statements: &[Statement]) -> bool {
+	statements.iter().any(|statement| match &statement.kind {
+		StatementKind::Return(_) => true,
+		StatementKind::If { branch, else_branch, .. } => always_returns(branch) && always_returns(else_branch),
+		// the typecheck pass makes sure that one of the arms runs
+		StatementKind::Match { arms, .. } => !arms.is_empty() && arms.iter().all(|arm| always_returns(&arm.statements)),
+		StatementKind::InfiniteLoop { label, loop_statements } => !breaks_out(loop_statements, label, false),
+		_ => false
+	})
+}
+
+// whether a break in the body of a loop with the label leaves it, where `nested` is whether the break would be inside another loop
+fn breaks_out(statements: &[Statement], label: &Option<String>, nested: bool) -> bool {
+	statements.iter().any(|statement| match &statement.kind {
+		StatementKind::Break(None) => !nested,
+		StatementKind::Break(target) => target == label,
+		StatementKind::If { branch, else_branch, .. } => breaks_out(branch, label, nested) || breaks_out(else_branch, label, nested),
+		StatementKind::Match { arms, .. } => arms.iter().any(|arm| breaks_out(&arm.statements, label, nested)),
+		StatementKind::While { label: inner, loop_statements, .. }
+		| StatementKind::InfiniteLoop { label: inner, loop_statements }
+		| StatementKind::For { label: inner, loop_statements, .. } => {
+			// a loop with the same label hides this one
+			(label.is_none() || inner != label) && breaks_out(loop_statements, label, true)
+		},
+		_ => false
+	})
+}
+
+// a global that the program can use, where a const has no value if its declaration has an error that has already been reported
+enum Global {
+	Const(Option<Constant>, AbstractType),
+	Static(AbstractType)
+}
+
+impl Global {
+	fn data_type(&self) -> &AbstractType {
+		match self {
+			Global::Const(_, data_type) | Global::Static(data_type) => data_type
+		}
+	}
+}
+
+/// Checks every global, statement and function of a file, returning all errors found.
+///
+/// Every node of a file that typechecks has its `data_type` filled in, and the body of every closure is moved into `functions`.
+/// Every use of a const is replaced by its value, and only the statics are left in `globals`.
+pub fn typecheck(file_description: &mut FileDescription) -> Result<(), Vec<TypeError>> {
+	let mut errors = vec![];
+
+	let signatures: HashMap<String, Signature> = file_description.functions.iter().map(|(name, info)| {
+		(name.clone(), (info.args.iter().map(|arg| arg.data_type.clone()).collect(), info.return_type.clone()))
+	}).collect();
+
+	let types = &file_description.types;
+	for (name, info) in types.iter() {
+		check_declaration(name, info, types, &mut errors);
+	}
+
+	let mut lifted = vec![];
+
+	// a global can only use the globals declared before it, which are the ones that are initialized before it
+	let mut globals = HashMap::new();
+	for info in file_description.globals.iter_mut() {
+		let error_count = errors.len();
+		let mut global_state = FunctionState::new(AbstractType::Void, &[], &signatures, types, &globals, &mut lifted, &mut errors);
+		global_state.in_static = !info.constant;
+		global_state.check_global(info);
+
+		let global = match info.constant {
+			true if errors.len() > error_count => Global::Const(None, info.data_type.clone()),
+			true => match constant::evaluate(&info.value) {
+				Ok(value) => Global::Const(Some(value), info.data_type.clone()),
+				Err((kind, span)) => {
+					errors.push(TypeError {
+						kind,
+						span
+					});
+					Global::Const(None, info.data_type.clone())
+				}
+			},
+			false => Global::Static(info.data_type.clone())
+		};
+		globals.insert(info.name.clone(), global);
+	}
+
+	// consts have been replaced by their values wherever they are used
+	file_description.globals.retain(|info| !info.constant);
+
+	let mut main_state = FunctionState::new(AbstractType::Void, &[], &signatures, types, &globals, &mut lifted, &mut errors);
+	main_state.check_statements(&mut file_description.statements);
+
+	for (name, info) in file_description.functions.iter_mut() {
+		if std_signature(name).is_some() || is_array_function(name) {
+			errors.push(TypeError {
+				kind: TypeErrorKind::RedefinedStdFunction(name.to_string()),
+				span: info.span
+			});
+		}
+
+		// the runtime's own symbols, like zinc_main and zinc_array_new, share the namespace of the program's functions
+		if name.starts_with("zinc_") {
+			errors.push(TypeError {
+				kind: TypeErrorKind::ReservedFunctionName(name.to_string()),
+				span: info.span
+			});
+		}
+
+		if let Some(kind) = invalid_type(&info.return_type, types) {
+			errors.push(TypeError {
+				kind,
+				span: info.span
+			});
+		}
+
+		let mut function_state = FunctionState::new(info.return_type.clone(), &info.args, &signatures, types, &globals, &mut lifted, &mut errors);
+		function_state.check_body(&mut info.body, info.span);
+	}
+
+	// closures are checked where they are written, and only then become functions of their own
+	file_description.functions.extend(lifted);
+
+	// functions are stored in a HashMap, so sort to report errors in source order
+	errors.sort_by_key(|error| error.span.start);
+
+	match errors.is_empty() {
+		true => Ok(()),
+		false => Err(errors)
+	}
+}
+
+// the operand and result types of a binary operator
+enum Operands {
+	// both operands are the same integer or float type, which is also the result
+	Arithmetic,
+	// both operands are the same integer type, which is also the result
+	Integral,
+	// both operands are the same integer or float type, and the result is a bool
+	Comparison,
+	// both operands and the result are bools
+	Logical
+}
+
+// a closure whose body is being checked
+struct ClosureScope {
+	// the number of scopes outside of the closure, whose variables it captures
+	depth: usize,
+	captures: Vec<Definition>,
+	span: Span
+}
+
+struct FunctionState<'a> {
+	return_type: AbstractType,
+	// innermost scope last
+	scopes: Vec<HashMap<String, AbstractType>>,
+	// labels of the enclosing loops, innermost last
+	loops: Vec<Option<String>>,
+	signatures: &'a HashMap<String, Signature>,
+	types: &'a TypeMap,
+	globals: &'a HashMap<String, Global>,
+	// whether this is the initializer of a static
+	in_static: bool,
+	// variables whose type couldn't be inferred because their value has an error
+	uninferred: HashSet<String>,
+	// innermost closure last
+	closures: Vec<ClosureScope>,
+	lifted: &'a mut Vec<(String, FunctionInfo)>,
+	errors: &'a mut Vec<TypeError>
+}
+
+impl<'a> FunctionState<'a> {
+	fn new(return_type: AbstractType, args: &[Definition], signatures: &'a HashMap<String, Signature>, types: &'a TypeMap, globals: &'a HashMap<String, Global>, lifted: &'a mut Vec<(String, FunctionInfo)>, errors: &'a mut Vec<TypeError>) -> Self {
+		let mut inst = Self {
+			return_type,
+			scopes: vec![HashMap::new()],
+			loops: vec![],
+			signatures,
+			types,
+			globals,
+			in_static: false,
+			uninferred: HashSet::new(),
+			closures: vec![],
+			lifted,
+			errors
+		};
+
+		for arg in args.iter() {
+			if arg.data_type == AbstractType::Void {
+				inst.error(TypeErrorKind::VoidVariable(arg.name.clone()), arg.span);
+			}
+			inst.check_type(&arg.data_type, arg.span);
+			inst.declare(arg.name.clone(), arg.data_type.clone());
+		}
+
+		return inst;
+	}
+
+	fn error(&mut self, kind: TypeErrorKind, span: Span) {
+		self.errors.push(TypeError {
+			kind,
+			span
+		});
+	}
+
+	// a variable whose type couldn't be inferred is left undeclared, but the error in its value has already been reported
+	fn undeclared(&mut self, kind: TypeErrorKind, name: &str, span: Span) {
+		if !self.uninferred.contains(name) {
+			self.error(kind, span);
+		}
+	}
+
+	// returns whether the type is valid, reporting an error if it isn't
+	fn check_type(&mut self, data_type: &AbstractType, span: Span) -> bool {
+		match invalid_type(data_type, self.types) {
+			Some(kind) => {
+				self.error(kind, span);
+				false
+			},
+			None => true
+		}
+	}
+
+	fn expect_type(&mut self, context: &'static str, expected: &AbstractType, node: &mut Node) {
+		// array literals take their element type from where they are used, so that they can be empty
+		if let (NodeKind::Array(elements), AbstractType::Array(element_type)) = (&mut node.kind, expected) {
+			for element in elements {
+				self.expect_type("array element", element_type, element);
+			}
+			node.data_type = Some(expected.clone());
+			return;
+		}
+
+		if let AbstractType::Integer(int_type) = expected {
+			self.type_literal(*int_type, node);
+		}
+
+		if let Some(found) = self.check_node(node) {
+			if found != *expected {
+				self.error(TypeErrorKind::MismatchedTypes {
+					context,
+					expected: expected.clone(),
+					found
+				}, node.span);
+			}
+		}
+	}
+
+	// an integer literal without a suffix takes the integer type it is used as, check_node then makes sure that its value fits
+	fn type_literal(&mut self, expected: IntegerType, node: &mut Node) {
+		if let NodeKind::Int { int_type, suffixed: false, .. } = &mut node.kind {
+			*int_type = expected;
+		}
+	}
+
+	// checks an operand that might be an unsuffixed literal, after the operand it should take its type from
+	fn check_operand(&mut self, other: &Option<AbstractType>, node: &mut Node) -> Option<AbstractType> {
+		if let Some(AbstractType::Integer(int_type)) = other {
+			self.type_literal(*int_type, node);
+		}
+		self.check_node(node)
+	}
+
+	fn signature(&self, name: &str) -> Option<Signature> {
+		std_signature(name).or_else(|| self.signatures.get(name).cloned())
+	}
+
+	fn declare(&mut self, name: String, var_type: AbstractType) {
+		self.scopes.last_mut().unwrap().insert(name, var_type);
+	}
+
+	// every closure between the variable and here captures it, but globals are shared rather than captured
+	fn lookup(&mut self, name: &str) -> Option<AbstractType> {
+		let (depth, var_type) = match self.scopes.iter().enumerate().rev().find_map(|(depth, scope)| {
+			scope.get(name).map(|var_type| (depth, var_type.clone()))
+		}) {
+			Some(variable) => variable,
+			None => return self.globals.get(name).map(|global| global.data_type().clone())
+		};
+
+		for closure in self.closures.iter_mut().filter(|closure| closure.depth > depth) {
+			if !closure.captures.iter().any(|capture| capture.name == name) {
+				closure.captures.push(Definition {
+					name: name.to_string(),
+					data_type: var_type.clone(),
+					span: closure.span
+				});
+			}
+		}
+
+		return Some(var_type);
+	}
+
+	// the const that a name refers to, unless a variable shadows it
+	fn constant(&self, name: &str) -> Option<&Global> {
+		match self.scopes.iter().any(|scope| scope.contains_key(name)) {
+			true => None,
+			false => self.globals.get(name).filter(|global| matches!(global, Global::Const(..)))
+		}
+	}
+
+	fn check_global(&mut self, info: &mut GlobalInfo) {
+		let valid = self.check_type(&info.data_type, info.span);
+
+		match &info.data_type {
+			AbstractType::Void => {
+				self.error(TypeErrorKind::VoidVariable(info.name.clone()), info.span);
+				self.check_node(&mut info.value);
+			},
+			data_type if valid => self.expect_type("global declaration", data_type, &mut info.value),
+			_ => {
+				self.check_node(&mut info.value);
+			}
+		}
+	}
+
+	fn is_captured(&self, name: &str) -> bool {
+		let depth = self.scopes.iter().rposition(|scope| scope.contains_key(name));
+		match (depth, self.closures.last()) {
+			(Some(depth), Some(closure)) => depth < closure.depth,
+			_ => false
+		}
+	}
+
+	fn check_statements(&mut self, statements: &mut [Statement]) {
+		for statement in statements {
+			self.check_statement(statement);
+		}
+	}
+
+	fn check_block(&mut self, statements: &mut [Statement]) {
+		self.scopes.push(HashMap::new());
+		self.check_statements(statements);
+		self.scopes.pop();
+	}
+
+	fn check_loop(&mut self, label: &Option<String>, statements: &mut [Statement]) {
+		self.loops.push(label.clone());
+		self.check_block(statements);
+		self.loops.pop();
+	}
+
+	fn check_jump(&mut self, keyword: &'static str, label: &Option<String>, span: Span) {
+		match label {
+			_ if self.loops.is_empty() => self.error(TypeErrorKind::OutsideOfLoop(keyword), span),
+			Some(label) if !self.loops.contains(&Some(label.clone())) => {
+				self.error(TypeErrorKind::UndefinedLabel(label.clone()), span);
+			},
+			_ => ()
+		}
+	}
+
+	fn check_statement(&mut self, statement: &mut Statement) {
+		match &mut statement.kind {
+			StatementKind::Node(node) => {
+				self.check_node(node);
+			},
+			StatementKind::Assign { name, value } => {
+				// closures get a copy of the variables they capture, so assigning to one would have no effect outside
+				if self.is_captured(name) {
+					self.error(TypeErrorKind::AssignToCapture(name.to_string()), statement.span);
+				}
+
+				if self.constant(name).is_some() {
+					self.error(TypeErrorKind::AssignToConst(name.to_string()), statement.span);
+				}
+
+				match self.lookup(name) {
+					Some(var_type) => self.expect_type("assignment", &var_type, value),
+					None => {
+						self.check_node(value);
+						self.undeclared(TypeErrorKind::UndefinedVariable(name.to_string()), name, statement.span);
+					}
+				}
+			},
+			StatementKind::AssignIndex { array, index, value } => {
+				if let Some(element_type) = self.check_index(array, index, statement.span) {
+					self.expect_type("assignment", &element_type, value);
+				} else {
+					self.check_node(value);
+				}
+			},
+			StatementKind::AssignField { structure, field, value } => {
+				if let Some(field_type) = self.check_field(structure, field, statement.span) {
+					self.expect_type("assignment", &field_type, value);
+				} else {
+					self.check_node(value);
+				}
+			},
+			StatementKind::Return(Some(node)) => {
+				let return_type = self.return_type.clone();
+				self.expect_type("return value", &return_type, node);
+			},
+			StatementKind::Return(None) => {
+				if self.return_type != AbstractType::Void {
+					self.error(TypeErrorKind::MissingReturnValue(self.return_type.clone()), statement.span);
+				}
+			},
+			StatementKind::If { condition, branch, else_branch } => {
+				self.expect_type("if condition", &AbstractType::Boolean, condition);
+				self.check_block(branch);
+				self.check_block(else_branch);
+			},
+			StatementKind::While { label, condition, loop_statements } => {
+				self.expect_type("while condition", &AbstractType::Boolean, condition);
+				self.check_loop(label, loop_statements);
+			},
+			StatementKind::InfiniteLoop { label, loop_statements } => {
+				self.check_loop(label, loop_statements);
+			},
+			StatementKind::For { label, variable, start, end, loop_statements, .. } => {
+				// the bounds can be any integer type, as long as they agree
+				let var_type = match self.check_node(start) {
+					Some(AbstractType::Integer(int_type)) => AbstractType::Integer(int_type),
+					Some(found) => {
+						self.error(TypeErrorKind::MismatchedTypes {
+							context: "range start",
+							expected: AbstractType::Integer(IntegerType::I32),
+							found
+						}, start.span);
+						AbstractType::Integer(IntegerType::I32)
+					},
+					None => AbstractType::Integer(IntegerType::I32)
+				};
+				self.expect_type("range end", &var_type, end);
+
+				self.scopes.push(HashMap::new());
+				self.declare(variable.clone(), var_type);
+				self.check_loop(label, loop_statements);
+				self.scopes.pop();
+			},
+			StatementKind::Match { value, arms } => self.check_match(value, arms, statement.span),
+			StatementKind::Break(label) => self.check_jump("break", label, statement.span),
+			StatementKind::Continue(label) => self.check_jump("continue", label, statement.span)
+		}
+	}
+
+	fn check_binary(&mut self, operator: &'static str, operands: Operands, lhs: &mut Node, rhs: &mut Node, span: Span) -> Option<AbstractType> {
+		// an unsuffixed literal takes the type of the other side, so that `byte + 1` adds two u8s
+		let (lhs, rhs) = match (unsuffixed(lhs), unsuffixed(rhs)) {
+			(true, false) => {
+				let rhs = self.check_node(rhs);
+				(self.check_operand(&rhs, lhs), rhs)
+			},
+			_ => {
+				let lhs = self.check_node(lhs);
+				(lhs.clone(), self.check_operand(&lhs, rhs))
+			}
+		};
+
+		// both sides are checked before giving up, so that an error in each of them is reported
+		let (lhs, rhs) = match (lhs, rhs) {
+			(Some(lhs), Some(rhs)) => (lhs, rhs),
+			_ => return None
+		};
+
+		let valid = match (&operands, &lhs) {
+			(Operands::Arithmetic | Operands::Comparison, AbstractType::Integer(_) | AbstractType::Float) => lhs == rhs,
+			(Operands::Integral, AbstractType::Integer(_)) => lhs == rhs,
+			(Operands::Logical, AbstractType::Boolean) => lhs == rhs,
+			_ => false
+		};
+
+		if !valid {
+			self.error(TypeErrorKind::InvalidOperands {
+				operator,
+				lhs,
+				rhs
+			}, span);
+			return None;
+		}
+
+		match operands {
+			Operands::Arithmetic | Operands::Integral => Some(lhs),
+			Operands::Comparison | Operands::Logical => Some(AbstractType::Boolean)
+		}
+	}
+
+	fn check_unary(&mut self, operator: &'static str, operand: &mut Node, span: Span) -> Option<AbstractType> {
+		let operand = self.check_node(operand)?;
+
+		let valid = match (operator, &operand) {
+			("-", AbstractType::Integer(int_type)) => int_type.is_signed(),
+			("-", AbstractType::Float) => true,
+			("!", AbstractType::Boolean) => true,
+			_ => false
+		};
+
+		if !valid {
+			self.error(TypeErrorKind::InvalidOperand {
+				operator,
+				operand
+			}, span);
+			return None;
+		}
+
+		return Some(operand);
+	}
+
+	// returns the element type
+	fn check_index(&mut self, array: &mut Node, index: &mut Node, span: Span) -> Option<AbstractType> {
+		let array_type = self.check_node(array);
+		self.expect_type("array index", &AbstractType::Integer(IntegerType::I32), index);
+
+		match array_type? {
+			AbstractType::Array(element_type) => Some(*element_type),
+			operand => {
+				self.error(TypeErrorKind::InvalidOperand {
+					operator: "[]",
+					operand
+				}, span);
+				None
+			}
+		}
+	}
+
+	// returns the type of the field
+	fn check_field(&mut self, structure: &mut Node, field: &str, span: Span) -> Option<AbstractType> {
+		let found = self.check_node(structure)?;
+
+		let field_type = match &found {
+			AbstractType::Named(name) => match self.types.get(name) {
+				Some(TypeInfo::Struct(info)) => info.fields.iter().find(|definition| definition.name == field),
+				_ => None
+			}.map(|definition| definition.data_type.clone()),
+			_ => None
+		};
+
+		if field_type.is_none() {
+			self.error(TypeErrorKind::NoField {
+				found,
+				field: field.to_string()
+			}, span);
+		}
+
+		return field_type;
+	}
+
+	fn check_construct(&mut self, name: &str, fields: &mut [(String, Node)], span: Span) -> Option<AbstractType> {
+		let types = self.types;
+		let info = match types.get(name) {
+			Some(TypeInfo::Struct(info)) => info,
+			found => {
+				for (_, value) in fields.iter_mut() {
+					self.check_node(value);
+				}
+
+				let kind = match found {
+					Some(_) => TypeErrorKind::NotAStruct(name.to_string()),
+					None => TypeErrorKind::UnknownType(name.to_string())
+				};
+				self.error(kind, span);
+				return None;
+			}
+		};
+
+		let mut seen = HashSet::new();
+		for (field, value) in fields.iter_mut() {
+			match info.fields.iter().find(|definition| definition.name == *field) {
+				_ if !seen.insert(field.clone()) => {
+					self.check_node(value);
+					self.error(TypeErrorKind::DuplicateField(field.clone()), value.span);
+				},
+				Some(definition) => self.expect_type("struct field", &definition.data_type, value),
+				None => {
+					self.check_node(value);
+					self.error(TypeErrorKind::NoField {
+						found: AbstractType::Named(name.to_string()),
+						field: field.clone()
+					}, value.span);
+				}
+			}
+		}
+
+		for definition in info.fields.iter() {
+			if !seen.contains(&definition.name) {
+				self.error(TypeErrorKind::MissingField {
+					structure: name.to_string(),
+					field: definition.name.clone()
+				}, span);
+			}
+		}
+
+		Some(AbstractType::Named(name.to_string()))
+	}
+
+	// checks the body of a function or closure, which has to return a value on every path unless it returns void
+	fn check_body(&mut self, body: &mut [Statement], span: Span) {
+		self.check_statements(body);
+
+		// a return type that doesn't exist has been reported already
+		let valid = invalid_type(&self.return_type, self.types).is_none();
+		if valid && self.return_type != AbstractType::Void && !always_returns(body) {
+			self.error(TypeErrorKind::MissingReturn(self.return_type.clone()), span);
+		}
+	}
+
+	fn check_closure(&mut self, args: &[Definition], return_type: &AbstractType, body: &mut Vec<Statement>, span: Span) -> (String, Vec<Definition>) {
+		for arg in args.iter() {
+			if arg.data_type == AbstractType::Void {
+				self.error(TypeErrorKind::VoidVariable(arg.name.clone()), arg.span);
+			}
+			self.check_type(&arg.data_type, arg.span);
+		}
+		self.check_type(return_type, span);
+
+		// the body is checked like a function of its own, except that it can see the enclosing variables
+		let outer_return_type = std::mem::replace(&mut self.return_type, return_type.clone());
+		let outer_loops = std::mem::take(&mut self.loops);
+		self.closures.push(ClosureScope {
+			depth: self.scopes.len(),
+			captures: vec![],
+			span
+		});
+
+		self.scopes.push(args.iter().map(|arg| (arg.name.clone(), arg.data_type.clone())).collect());
+		self.check_body(body, span);
+		self.scopes.pop();
+
+		let captures = self.closures.pop().unwrap().captures;
+		self.loops = outer_loops;
+		self.return_type = outer_return_type;
+
+		// "." can't appear in a name, so this can't clash with a function of the program
+		let name = format!("closure.{}", self.lifted.len());
+		self.lifted.push((name.clone(), FunctionInfo {
+			body: std::mem::take(body),
+			args: args.to_vec(),
+			return_type: return_type.clone(),
+			span,
+			captures: Some(captures.clone())
+		}));
+
+		return (name, captures);
+	}
+
+	// returns the payload types of a variant, reporting an error if it doesn't exist
+	fn variant_fields(&mut self, enum_name: &str, variant: &str, span: Span) -> Option<&'a [AbstractType]> {
+		let types = self.types;
+		let info = match types.get(enum_name) {
+			Some(TypeInfo::Enum(info)) => info,
+			Some(_) => {
+				self.error(TypeErrorKind::NotAnEnum(enum_name.to_string()), span);
+				return None;
+			},
+			None => {
+				self.error(TypeErrorKind::UnknownType(enum_name.to_string()), span);
+				return None;
+			}
+		};
+
+		match info.variant(variant) {
+			Some((_, variant_info)) => Some(&variant_info.fields),
+			None => {
+				self.error(TypeErrorKind::UnknownVariant {
+					enum_name: enum_name.to_string(),
+					variant: variant.to_string()
+				}, span);
+				None
+			}
+		}
+	}
+
+	fn check_variant(&mut self, enum_name: &str, variant: &str, values: &mut [Node], span: Span) -> Option<AbstractType> {
+		let fields = match self.variant_fields(enum_name, variant, span) {
+			Some(fields) if fields.len() == values.len() => fields,
+			fields => {
+				for value in values.iter_mut() {
+					self.check_node(value);
+				}
+
+				if let Some(fields) = fields {
+					self.error(TypeErrorKind::IncorrectPayloadNumber {
+						variant: format!("{}::{}", enum_name, variant),
+						expected: fields.len(),
+						found: values.len()
+					}, span);
+				}
+				return None;
+			}
+		};
+
+		for (expected, value) in fields.iter().zip(values) {
+			self.expect_type("variant payload", expected, value);
+		}
+
+		Some(AbstractType::Named(enum_name.to_string()))
+	}
+
+	// declares the bindings of the pattern, and returns what it covers so that exhaustiveness can be checked
+	fn check_pattern(&mut self, pattern: &mut Pattern, expected: &Option<AbstractType>, span: Span) -> Option<String> {
+		let (found, covered) = match pattern {
+			Pattern::Wildcard => return Some(String::from("_")),
+			Pattern::Literal(node) => {
+				let found = self.check_operand(expected, node)?;
+				let covered = match &node.kind {
+					NodeKind::Int { value, .. } => value.to_string(),
+					NodeKind::StringLiteral(value) => format!("{:?}", value),
+					NodeKind::Bool(value) => value.to_string(),
+					_ => unreachable!("patterns should only contain literals")
+				};
+				(found, covered)
+			},
+			Pattern::Variant { enum_name, variant, bindings } => {
+				let fields = self.variant_fields(enum_name, variant, span)?;
+				let covered = format!("{}::{}", enum_name, variant);
+
+				if fields.len() != bindings.len() {
+					self.error(TypeErrorKind::IncorrectPayloadNumber {
+						variant: covered,
+						expected: fields.len(),
+						found: bindings.len()
+					}, span);
+					return None;
+				}
+
+				for (binding, data_type) in bindings.iter().zip(fields) {
+					if let Some(name) = binding {
+						self.declare(name.clone(), data_type.clone());
+					}
+				}
+				(AbstractType::Named(enum_name.clone()), covered)
+			}
+		};
+
+		match expected {
+			Some(expected) if found != *expected => {
+				self.error(TypeErrorKind::MismatchedTypes {
+					context: "match pattern",
+					expected: expected.clone(),
+					found
+				}, span);
+				None
+			},
+			_ => Some(covered)
+		}
+	}
+
+	fn check_match(&mut self, value: &mut Node, arms: &mut [MatchArm], span: Span) {
+		let value_type = self.check_node(value);
+
+		let mut covered = HashSet::new();
+		for arm in arms.iter_mut() {
+			self.scopes.push(HashMap::new());
+			if let Some(pattern) = self.check_pattern(&mut arm.pattern, &value_type, arm.span) {
+				if covered.contains("_") || !covered.insert(pattern) {
+					self.error(TypeErrorKind::UnreachablePattern, arm.span);
+				}
+			}
+			self.check_statements(&mut arm.statements);
+			self.scopes.pop();
+		}
+
+		// a wildcard is the only way to cover every integer or string
+		let required: Vec<String> = match &value_type {
+			_ if covered.contains("_") => return,
+			None => return,
+			Some(AbstractType::Named(name)) => match &self.types[name] {
+				TypeInfo::Enum(info) => info.variants.iter().map(|variant| format!("{}::{}", name, variant.name)).collect(),
+				TypeInfo::Struct(_) => vec![String::from("_")]
+			},
+			Some(AbstractType::Boolean) => vec![String::from("true"), String::from("false")],
+			Some(_) => vec![String::from("_")]
+		};
+
+		let missing: Vec<String> = required.into_iter().filter(|pattern| !covered.contains(pattern)).collect();
+		if !missing.is_empty() {
+			self.error(TypeErrorKind::NonExhaustiveMatch(missing), span);
+		}
+	}
+
+	fn check_array_function(&mut self, name: &str, args: &mut [Node], span: Span) -> Option<AbstractType> {
+		let (expected_args, return_type) = match name {
+			"len" => (1, AbstractType::Integer(IntegerType::I32)),
+			_ => (2, AbstractType::Void)
+		};
+
+		if args.len() != expected_args {
+			for arg in args.iter_mut() {
+				self.check_node(arg);
+			}
+
+			self.error(TypeErrorKind::IncorrectArgumentNumber {
+				function: name.to_string(),
+				expected: expected_args,
+				found: args.len()
+			}, span);
+			return Some(return_type);
+		}
+
+		let element_type = match self.check_node(&mut args[0]) {
+			Some(AbstractType::Array(element_type)) => Some(*element_type),
+			Some(found) => {
+				self.error(TypeErrorKind::ExpectedArray {
+					function: name.to_string(),
+					found
+				}, args[0].span);
+				None
+			},
+			None => None
+		};
+
+		if let Some(value) = args.get_mut(1) {
+			match element_type {
+				Some(element_type) => self.expect_type("function argument", &element_type, value),
+				None => {
+					self.check_node(value);
+				}
+			}
+		}
+
+		return Some(return_type);
+	}
+
+	// returns None if the node contains an error that has already been reported
+	fn check_node(&mut self, node: &mut Node) -> Option<AbstractType> {
+		let data_type = self.check_node_kind(&mut node.kind, node.span);
+		node.data_type = data_type.clone();
+		return data_type;
+	}
+
+	fn check_node_kind(&mut self, node: &mut NodeKind, span: Span) -> Option<AbstractType> {
+		use AbstractType::*;
+		use Operands::*;
+
+		match node {
+			NodeKind::Int { value, int_type, .. } => {
+				// the parser has already checked the range of suffixed literals
+				if *value < int_type.min() || *value > int_type.max() {
+					self.error(TypeErrorKind::LiteralOutOfRange {
+						value: *value,
+						int_type: *int_type
+					}, span);
+					return None;
+				}
+				Some(Integer(*int_type))
+			},
+			NodeKind::Float(_) => Some(Float),
+			NodeKind::Add { lhs, rhs } => self.check_binary("+", Arithmetic, lhs, rhs, span),
+			NodeKind::Subtract { lhs, rhs } => self.check_binary("-", Arithmetic, lhs, rhs, span),
+			NodeKind::Multiply { lhs, rhs } => self.check_binary("*", Arithmetic, lhs, rhs, span),
+			NodeKind::Divide { lhs, rhs } => self.check_binary("/", Arithmetic, lhs, rhs, span),
+			NodeKind::Remainder { lhs, rhs } => self.check_binary("%", Integral, lhs, rhs, span),
+			NodeKind::BitAnd { lhs, rhs } => self.check_binary("&", Integral, lhs, rhs, span),
+			NodeKind::BitOr { lhs, rhs } => self.check_binary("|", Integral, lhs, rhs, span),
+			NodeKind::BitXor { lhs, rhs } => self.check_binary("^", Integral, lhs, rhs, span),
+			NodeKind::ShiftLeft { lhs, rhs } => self.check_binary("<<", Integral, lhs, rhs, span),
+			NodeKind::ShiftRight { lhs, rhs } => self.check_binary(">>", Integral, lhs, rhs, span),
+			NodeKind::Equal { lhs, rhs } => self.check_binary("==", Comparison, lhs, rhs, span),
+			NodeKind::NotEqual { lhs, rhs } => self.check_binary("!=", Comparison, lhs, rhs, span),
+			NodeKind::LessThanOrEqual { lhs, rhs } => self.check_binary("<=", Comparison, lhs, rhs, span),
+			NodeKind::GreaterThanOrEqual { lhs, rhs } => self.check_binary(">=", Comparison, lhs, rhs, span),
+			NodeKind::LessThan { lhs, rhs } => self.check_binary("<", Comparison, lhs, rhs, span),
+			NodeKind::GreaterThan { lhs, rhs } => self.check_binary(">", Comparison, lhs, rhs, span),
+			NodeKind::Negate(operand) => self.check_unary("-", operand, span),
+			NodeKind::Cast { value, target } => {
+				let from = self.check_node(value)?;
+
+				match (&from, &target) {
+					(Integer(_) | Float, Integer(_) | Float) => Some(target.clone()),
+					_ => {
+						self.error(TypeErrorKind::InvalidCast {
+							from,
+							to: target.clone()
+						}, span);
+						None
+					}
+				}
+			},
+
+			NodeKind::Bool(_) => Some(Boolean),
+			NodeKind::BoolEqual { lhs, rhs } => self.check_binary("=?", Logical, lhs, rhs, span),
+			NodeKind::BoolNotEqual { lhs, rhs } => self.check_binary("!?", Logical, lhs, rhs, span),
+			NodeKind::Not(operand) => self.check_unary("!", operand, span),
+			NodeKind::And { lhs, rhs } => self.check_binary("&&", Logical, lhs, rhs, span),
+			NodeKind::Or { lhs, rhs } => self.check_binary("||", Logical, lhs, rhs, span),
+
+			NodeKind::StringLiteral(_) => Some(String),
+			NodeKind::Interpolation(parts) => {
+				for part in parts {
+					match self.check_node(part) {
+						Some(Integer(_) | Float | Boolean | String) | None => (),
+						Some(found) => self.error(TypeErrorKind::InvalidInterpolation(found), part.span)
+					}
+				}
+
+				Some(String)
+			},
+
+			NodeKind::Array(elements) => {
+				let (first, rest) = match elements.split_first_mut() {
+					Some(elements) => elements,
+					None => {
+						self.error(TypeErrorKind::EmptyArray, span);
+						return None;
+					}
+				};
+
+				let element_type = self.check_node(first)?;
+				if element_type == Void {
+					self.error(TypeErrorKind::VoidElement, first.span);
+					return None;
+				}
+
+				for element in rest {
+					self.expect_type("array element", &element_type, element);
+				}
+
+				Some(Array(Box::new(element_type)))
+			},
+			NodeKind::Index { array, index } => self.check_index(array, index, span),
+
+			NodeKind::Construct { name, fields } => self.check_construct(name, fields, span),
+			NodeKind::Field { structure, field } => self.check_field(structure, field, span),
+
+			NodeKind::Variant { enum_name, variant, values } => self.check_variant(enum_name, variant, values, span),
+
+			NodeKind::Set { name, var_type, value } => {
+				let var_type = match var_type {
+					Some(var_type) => {
+						match self.check_type(var_type, span) {
+							true => self.expect_type("variable declaration", var_type, value),
+							false => {
+								self.check_node(value);
+							}
+						}
+						var_type.clone()
+					},
+					None => match self.check_node(value) {
+						Some(inferred) => {
+							*var_type = Some(inferred.clone());
+							inferred
+						},
+						None => {
+							self.uninferred.insert(name.to_string());
+							return None;
+						}
+					}
+				};
+
+				if var_type == Void {
+					self.error(TypeErrorKind::VoidVariable(name.to_string()), span);
+					return None;
+				}
+
+				self.declare(name.to_string(), var_type.clone());
+				Some(var_type)
+			},
+			NodeKind::Get { name } => {
+				match self.constant(name) {
+					Some(Global::Const(Some(value), data_type)) => {
+						let data_type = data_type.clone();
+						*node = value.to_node_kind();
+						return Some(data_type);
+					},
+					Some(_) => return Some(self.globals[name.as_str()].data_type().clone()),
+					None => ()
+				}
+
+				// variables shadow functions, which can be used as values too
+				let function_type = self.signature(name).map(|(args, return_type)| AbstractType::Function {
+					args,
+					return_type: Box::new(return_type)
+				});
+
+				match self.lookup(name).or(function_type) {
+					Some(var_type) => Some(var_type),
+					None => {
+						self.undeclared(TypeErrorKind::UndefinedVariable(name.to_string()), name, span);
+						None
+					}
+				}
+			},
+
+			NodeKind::Closure { args, return_type, body, name, captures } => {
+				(*name, *captures) = self.check_closure(args, return_type, body, span);
+
+				Some(Function {
+					args: args.iter().map(|arg| arg.data_type.clone()).collect(),
+					return_type: Box::new(return_type.clone())
+				})
+			},
+
+			NodeKind::Function { name, args } if is_array_function(name) && self.lookup(name).is_none() => self.check_array_function(name, args, span),
+			NodeKind::Function { name, args } => {
+				// the initializers of statics run before the rest of the program, and a function could read a static that comes later
+				if self.in_static && self.closures.is_empty() && (self.lookup(name).is_some() || std_signature(name).is_none()) {
+					self.error(TypeErrorKind::CallInStatic(name.to_string()), span);
+				}
+
+				let signature = match self.lookup(name) {
+					Some(AbstractType::Function { args, return_type }) => Some((args, *return_type)),
+					Some(found) => {
+						for arg in args {
+							self.check_node(arg);
+						}
+
+						self.error(TypeErrorKind::NotAFunction {
+							name: name.to_string(),
+							found
+						}, span);
+						return None;
+					},
+					None => self.signature(name)
+				};
+
+				let (expected_args, return_type) = match signature {
+					Some(signature) => signature,
+					None => {
+						for arg in args {
+							self.check_node(arg);
+						}
+
+						self.undeclared(TypeErrorKind::UndefinedFunction(name.to_string()), name, span);
+						return None;
+					}
+				};
+
+				if expected_args.len() != args.len() {
+					for arg in args.iter_mut() {
+						self.check_node(arg);
+					}
+
+					self.error(TypeErrorKind::IncorrectArgumentNumber {
+						function: name.to_string(),
+						expected: expected_args.len(),
+						found: args.len()
+					}, span);
+				} else {
+					for (expected, arg) in expected_args.iter().zip(args) {
+						self.expect_type("function argument", expected, arg);
+					}
+				}
+
+				Some(return_type)
+			}
+		}
+	}
+}
+
+// the message and line and column of every error in a program that should fail to typecheck
+#[cfg(test)]
+fn type_errors(source: &str) -> Vec<(String, (usize, usize))> {
+	let mut file_description = crate::parse::parse(source).expect("failed to parse");
+	return typecheck(&mut file_description).unwrap_err().iter().map(|err| {
+		(err.to_string(), err.span.line_col(source))
+	}).collect();
+}
+
+#[test]
+fn test_typecheck() {
+	let source = r#"
+		let x: i32 = true;
+		let y: bool = 1 + 2 == 3;
+		y = 5;
+		assert_int_eq(1);
+		assert_bool_eq(y, 1 < z);
+		not_a_function();
+		if (1) {
+			let w: str = "bruh";
+			print_str(w);
+		}
+		w = "bruh";
+
+		fn half(n: i32) -> i32 {
+			return n =? true;
+		}
+
+		fn print_int(n: i32) -> void {
+			print_bool(n);
+		}
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("mismatched types in variable declaration, expected `i32` but found `bool`"), (2, 16)),
+		(String::from("mismatched types in assignment, expected `bool` but found `i32`"), (4, 7)),
+		(String::from("function `assert_int_eq` takes 2 argument(s) but 1 were supplied"), (5, 3)),
+		(String::from("undeclared variable `z`"), (6, 25)),
+		(String::from("undefined function `not_a_function`"), (7, 3)),
+		(String::from("mismatched types in if condition, expected `bool` but found `i32`"), (8, 7)),
+		(String::from("undeclared variable `w`"), (12, 3)),
+		(String::from("operator `=?` cannot be applied to `i32` and `bool`"), (15, 11)),
+		(String::from("function `print_int` is already defined by the standard library"), (18, 6)),
+		(String::from("mismatched types in function argument, expected `bool` but found `i32`"), (19, 15))
+	]);
+
+	let mut file_description = crate::parse::parse(r#"
+		fn fibo(n: i32) -> i32 {
+			if (n <= 1) {
+				return n;
+			} else {
+				return fibo(n - 1) + fibo(n - 2);
+			}
+		}
+
+		let s: str = str_concat("br", "uh");
+		assert_int_eq(fibo(str_len(s)), 3);
+	"#).expect("failed to parse");
+
+	typecheck(&mut file_description).expect("valid program should typecheck");
+
+	let source = "let x: i32 = (true + 1) + (false + 2);";
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("operator `+` cannot be applied to `bool` and `i32`"), (1, 15)),
+		(String::from("operator `+` cannot be applied to `bool` and `i32`"), (1, 28))
+	]);
+}
+
+#[test]
+fn test_reserved_names() {
+	let source = "fn zinc_array_len(x: i32) -> void {}";
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("function `zinc_array_len` cannot be defined, names starting with `zinc_` are reserved for the runtime"), (1, 4))
+	]);
+}
+
+#[test]
+fn test_loop_errors() {
+	let source = r#"
+		break;
+		'outer: loop {
+			while (true) {
+				continue 'inner;
+			}
+			break 'outer;
+		}
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("`break` outside of a loop"), (2, 3)),
+		(String::from("undeclared label `'inner`"), (5, 5))
+	]);
+}
+
+#[test]
+fn test_integer_errors() {
+	let source = r#"
+		let x: i64 = 5i32;
+		let y: u8 = 5u8 + 1i32;
+		let z: u32 = -(1u32);
+		let w: bool = true as i32 == 1;
+		let v: u16 = 1u16 as u16 as u64;
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("mismatched types in variable declaration, expected `i64` but found `i32`"), (2, 16)),
+		(String::from("operator `+` cannot be applied to `u8` and `i32`"), (3, 15)),
+		(String::from("operator `-` cannot be applied to `u32`"), (4, 16)),
+		(String::from("cannot cast `bool` as `i32`"), (5, 17)),
+		(String::from("mismatched types in variable declaration, expected `u16` but found `u64`"), (6, 16))
+	]);
+}
+
+#[test]
+fn test_float_errors() {
+	let source = r#"
+		let x: f64 = 1;
+		let y: f64 = 1.5 % 2.0;
+		let z: f64 = 2.0 * 3 as f64;
+		let w: bool = 1.0 < 2;
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("mismatched types in variable declaration, expected `f64` but found `i32`"), (2, 16)),
+		(String::from("operator `%` cannot be applied to `f64` and `f64`"), (3, 16)),
+		(String::from("operator `<` cannot be applied to `f64` and `i32`"), (5, 17))
+	]);
+}
+
+#[test]
+fn test_array_errors() {
+	let source = r#"
+		let a: [i32] = [1, true];
+		let b: [bool] = [];
+		let c: i32 = [];
+		let d: bool = b[true];
+		let e: i32 = d[0];
+		push(b, 1);
+		let f: i32 = len(5);
+		let g: [[i32]] = [[], [1]];
+		g[0] = [];
+		g[0][0] = false;
+		let h: i32 = len([[], [1]]);
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("mismatched types in array element, expected `i32` but found `bool`"), (2, 22)),
+		(String::from("cannot infer the element type of an empty array"), (4, 16)),
+		(String::from("mismatched types in array index, expected `i32` but found `bool`"), (5, 19)),
+		(String::from("operator `[]` cannot be applied to `bool`"), (6, 16)),
+		(String::from("mismatched types in function argument, expected `bool` but found `i32`"), (7, 11)),
+		(String::from("function `len` expects an array but found `i32`"), (8, 20)),
+		(String::from("mismatched types in assignment, expected `i32` but found `bool`"), (11, 13)),
+		(String::from("cannot infer the element type of an empty array"), (12, 21))
+	]);
+}
+
+#[test]
+fn test_struct_errors() {
+	let source = r#"
+		struct Point { x: i32, y: i32 }
+		struct Line { from: Point, to: Point, label: Label }
+		struct i64 { value: i32 }
+		struct Bad { a: void, b: [void], a: i32 }
+
+		let p: Point = Point { x: 1, y: true };
+		let q: Point = Point { x: 1, x: 2, z: 3 };
+		let r: u128 = 5;
+		p.z = 4;
+		p.x = p.y.x;
+		let s: Nope = Nope { a: 1 };
+		fn f(p: Pointy) -> Lines {}
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("unknown type `Label`"), (3, 41)),
+		(String::from("type `i64` is already built into the language"), (4, 10)),
+		(String::from("field `a` cannot be void"), (5, 16)),
+		(String::from("array elements cannot be void"), (5, 25)),
+		(String::from("field `a` is listed more than once"), (5, 36)),
+		(String::from("mismatched types in struct field, expected `i32` but found `bool`"), (7, 35)),
+		(String::from("missing field `y` in constructor of `Point`"), (8, 18)),
+		(String::from("field `x` is listed more than once"), (8, 35)),
+		(String::from("no field `z` on type `Point`"), (8, 41)),
+		(String::from("unknown type `u128`"), (9, 3)),
+		(String::from("no field `z` on type `Point`"), (10, 3)),
+		(String::from("no field `x` on type `i32`"), (11, 9)),
+		(String::from("unknown type `Nope`"), (12, 3)),
+		(String::from("unknown type `Nope`"), (12, 17)),
+		(String::from("unknown type `Lines`"), (13, 6)),
+		(String::from("unknown type `Pointy`"), (13, 8))
+	]);
+}
+
+#[test]
+fn test_enum_errors() {
+	let source = r#"
+		enum Color { Red, Green, Red, Custom(void), Named(Label) }
+		enum Shape { Circle(f64), Square(f64) }
+		struct Point { x: i32 }
+
+		let s: Shape = Shape::Circle(1);
+		let t: Shape = Shape::Triangle;
+		let u: Shape = Shape::Square;
+		let p: Point = Point::Origin;
+		let q: Shape = Shape { x: 1 };
+		match (s) {
+			Shape::Circle(r) => {
+				let d: f64 = r * 2.0;
+			}
+			Color::Red => {}
+			Shape::Circle(_) => {}
+		}
+		match (5) {
+			1 => {}
+			"one" => {}
+		}
+		match (true) {
+			true => {}
+			_ => {}
+			false => {}
+		}
+		match (false) {
+			false => {}
+		}
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("variant `Red` is listed more than once"), (2, 28)),
+		(String::from("variant `Custom` cannot carry void"), (2, 33)),
+		(String::from("unknown type `Label`"), (2, 47)),
+		(String::from("mismatched types in variant payload, expected `f64` but found `i32`"), (6, 32)),
+		(String::from("no variant `Triangle` in enum `Shape`"), (7, 18)),
+		(String::from("variant `Shape::Square` carries 1 value(s) but 0 were supplied"), (8, 18)),
+		(String::from("`Point` is not an enum"), (9, 18)),
+		(String::from("`Shape` is not a struct"), (10, 18)),
+		(String::from("match is not exhaustive, missing `Shape::Square`"), (11, 3)),
+		(String::from("mismatched types in match pattern, expected `Shape` but found `Color`"), (15, 4)),
+		(String::from("unreachable pattern"), (16, 4)),
+		(String::from("match is not exhaustive, missing `_`"), (18, 3)),
+		(String::from("mismatched types in match pattern, expected `i32` but found `str`"), (20, 4)),
+		(String::from("unreachable pattern"), (25, 4)),
+		(String::from("match is not exhaustive, missing `true`"), (27, 3))
+	]);
+}
+
+#[test]
+fn test_function_values() {
+	let source = r#"
+		fn add(a: i32, b: i32) -> i32 {
+			return a + b;
+		}
+
+		let f: fn(i32, i32) -> i32 = add;
+		let g: fn(i32) -> i32 = add;
+		let h: fn(void) -> i32 = add;
+		let n: i32 = 5;
+		n(1);
+		f(1, true);
+		let p: fn(str) -> void = print_str;
+		let q: fn(i32) -> i32 = len;
+		let r: bool = f(1, 2);
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("mismatched types in variable declaration, expected `fn(i32) -> i32` but found `fn(i32, i32) -> i32`"), (7, 27)),
+		(String::from("function parameters cannot be void"), (8, 3)),
+		(String::from("variable `n` of type `i32` cannot be called"), (10, 3)),
+		(String::from("mismatched types in function argument, expected `i32` but found `bool`"), (11, 8)),
+		(String::from("undeclared variable `len`"), (13, 27)),
+		(String::from("mismatched types in variable declaration, expected `bool` but found `i32`"), (14, 17))
+	]);
+}
+
+#[test]
+fn test_closures() {
+	let source = r#"
+		let n: i32 = 1;
+		let f: fn(i32) -> bool = |x: i32| -> bool {
+			n = x;
+			return x + n;
+		};
+		loop {
+			let g: fn() -> void = || -> void {
+				break;
+			};
+		}
+		let h: fn(void) -> i32 = |x: void| -> i32 { return y; };
+		fn outside() -> i32 {
+			return n;
+		}
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("cannot assign to `n`, which is captured by a closure"), (4, 4)),
+		(String::from("mismatched types in return value, expected `bool` but found `i32`"), (5, 11)),
+		(String::from("`break` outside of a loop"), (9, 5)),
+		(String::from("function parameters cannot be void"), (12, 3)),
+		(String::from("variable `x` cannot be void"), (12, 29)),
+		(String::from("undeclared variable `y`"), (12, 54)),
+		(String::from("undeclared variable `n`"), (14, 11))
+	]);
+
+	let mut file_description = crate::parse::parse(r#"
+		let a: i32 = 1;
+		let f: fn() -> fn() -> i32 = || -> fn() -> i32 {
+			let b: i32 = 2;
+			return || -> i32 { return a + b; };
+		};
+	"#).expect("failed to parse");
+	typecheck(&mut file_description).expect("valid program should typecheck");
+
+	let mut captures: Vec<_> = file_description.functions.iter().map(|(name, info)| {
+		(name.as_str(), info.captures.iter().flatten().map(|capture| capture.name.as_str()).collect::<Vec<_>>())
+	}).collect();
+	captures.sort();
+	assert_eq!(captures, vec![("closure.0", vec!["a", "b"]), ("closure.1", vec!["a"])]);
+}
+
+#[test]
+fn test_literal_types() {
+	let source = r#"
+		let a: i64 = 3000000000;
+		let b: u8 = 256;
+		let bytes: [u8] = [];
+		push(bytes, 255);
+		push(bytes, -1);
+		let c: u8 = bytes[0] + 1;
+		let d: bool = bytes[0] > 300;
+		let e = 2147483648;
+		match (c) { 255 => {} 0 => {} 1000 => {} _ => {} }
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("integer literal `256` does not fit in `u8`"), (3, 15)),
+		(String::from("integer literal `-1` does not fit in `u8`"), (6, 15)),
+		(String::from("integer literal `300` does not fit in `u8`"), (8, 28)),
+		(String::from("integer literal `2147483648` does not fit in `i32`"), (9, 11)),
+		(String::from("integer literal `1000` does not fit in `u8`"), (10, 33))
+	]);
+
+	// the literals take the type they are used as, for the later passes
+	let mut file_description = crate::parse::parse("let a: i64 = 5; let b: u16 = 1 as u16; b = b * 7; let c = 1;").expect("failed to parse");
+	typecheck(&mut file_description).expect("valid program should typecheck");
+	let mut types = vec![];
+	for statement in file_description.statements.iter() {
+		let value = match &statement.kind {
+			StatementKind::Node(Node { kind: NodeKind::Set { value, .. }, .. }) => value,
+			StatementKind::Assign { value, .. } => value,
+			_ => unreachable!()
+		};
+		let literal = match &value.kind {
+			NodeKind::Multiply { rhs, .. } => rhs,
+			_ => value
+		};
+		types.push(literal.data_type.clone());
+	}
+	assert_eq!(types, [
+		Some(AbstractType::Integer(IntegerType::I64)),
+		Some(AbstractType::Integer(IntegerType::U16)),
+		Some(AbstractType::Integer(IntegerType::U16)),
+		Some(AbstractType::Integer(IntegerType::I32))
+	]);
+}
+
+#[test]
+fn test_returns() {
+	let source = r#"
+		fn sign(n: i32) -> i32 {
+			if (n < 0) {
+				return -1;
+			} else {
+				if (n > 0) {
+					return 1;
+				}
+			}
+		}
+		fn first_even(values: [i32]) -> i32 {
+			for i in 0..len(values) {
+				if (values[i] % 2 == 0) {
+					return values[i];
+				}
+			}
+		}
+		fn search(n: i32) -> i32 {
+			'outer: loop {
+				loop {
+					break 'outer;
+				}
+			}
+		}
+		fn bare() -> bool {
+			return;
+		}
+		let f: fn() -> i32 = || -> i32 {
+			print_int(1);
+		};
+		fn nothing() -> void {
+			return print_int(1);
+		}
+		fn five() -> void {
+			return 5;
+		}
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("function can reach its end without returning a `i32`"), (2, 6)),
+		(String::from("function can reach its end without returning a `i32`"), (11, 6)),
+		(String::from("function can reach its end without returning a `i32`"), (18, 6)),
+		(String::from("`return` without a value in a function that returns `bool`"), (26, 4)),
+		(String::from("function can reach its end without returning a `i32`"), (28, 24)),
+		(String::from("mismatched types in return value, expected `void` but found `i32`"), (35, 11))
+	]);
+
+	let mut file_description = crate::parse::parse(r#"
+		enum Shape { Circle(i32), Square(i32) }
+		fn area(shape: Shape) -> i32 {
+			match (shape) {
+				Shape::Circle(r) => { return 3 * r * r; }
+				Shape::Square(side) => { return side * side; }
+			}
+		}
+		fn parity(n: i32) -> bool {
+			if (n % 2 == 0) {
+				return true;
+			} else {
+				return false;
+			}
+		}
+		fn forever(n: i32) -> i32 {
+			'outer: loop {
+				while (n > 0) {
+					break;
+				}
+				loop {
+					continue 'outer;
+				}
+			}
+		}
+		fn early(n: i32) -> void {
+			if (n > 0) {
+				return;
+			}
+			print_int(n);
+		}
+	"#).expect("failed to parse");
+	typecheck(&mut file_description).expect("valid program should typecheck");
+}
+
+#[test]
+fn test_globals() {
+	let source = r#"
+		const A: i32 = 2147483647;
+		const B: i32 = A + 1;
+		const C: i32 = later;
+		static later: i32 = 1;
+		const D: [i32] = [1];
+		static E: i32 = f();
+		fn f() -> i32 {
+			A = 3;
+			return -(1 << 40);
+		}
+		const F: u8 = 7u8 >> 8u8;
+		static G: fn() -> i32 = || -> i32 { return f(); };
+		let A: i32 = 1;
+		A = 2;
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("cannot evaluate const, attempt to add with overflow"), (3, 18)),
+		(String::from("undeclared variable `later`"), (4, 18)),
+		(String::from("a const can only be made of literals, operators, casts and other consts"), (6, 20)),
+		(String::from("a static cannot be initialized by calling `f`, which could use statics that aren't initialized yet"), (7, 19)),
+		(String::from("cannot assign to const `A`"), (9, 4)),
+		(String::from("cannot evaluate const, attempt to shift by a negative amount or by more bits than the type has"), (12, 17))
+	]);
+
+	// uses of consts are replaced by their values, and only the statics are left
+	let mut file_description = crate::parse::parse(r#"
+		const LIMIT: u8 = 200u8 + 55u8;
+		const NEGATIVE: i64 = -(LIMIT as i64) * 2i64;
+		static total: i64 = NEGATIVE;
+		fn add(n: i64) -> void {
+			total = total + n;
+		}
+		print_i64(NEGATIVE);
+	"#).expect("failed to parse");
+	typecheck(&mut file_description).expect("valid program should typecheck");
+	let names: Vec<&str> = file_description.globals.iter().map(|global| global.name.as_str()).collect();
+	assert_eq!(names, ["total"]);
+	assert!(matches!(file_description.globals[0].value.kind, NodeKind::Int { value: -510, int_type: IntegerType::I64, .. }));
+}
+
+#[test]
+fn test_inference() {
+	let source = r#"
+		let empty = [];
+		let nothing = print_int(1);
+		let broken = 1 + true;
+		let copy = broken;
+		broken = 2;
+		let n = 5u8;
+		n = 300;
+	"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("cannot infer the element type of an empty array"), (2, 15)),
+		(String::from("variable `nothing` cannot be void"), (3, 3)),
+		(String::from("operator `+` cannot be applied to `i32` and `bool`"), (4, 16)),
+		(String::from("integer literal `300` does not fit in `u8`"), (8, 7))
+	]);
+
+	// the inferred type is filled in for the later passes
+	let mut file_description = crate::parse::parse("let values = [1.5, 2.0]; let first = values[0] as i64;").expect("failed to parse");
+	typecheck(&mut file_description).expect("valid program should typecheck");
+	let types: Vec<_> = file_description.statements.iter().map(|statement| match &statement.kind {
+		StatementKind::Node(Node { kind: NodeKind::Set { var_type, .. }, .. }) => var_type.clone(),
+		_ => None
+	}).collect();
+	assert_eq!(types, [Some(AbstractType::Array(Box::new(AbstractType::Float))), Some(AbstractType::Integer(IntegerType::I64))]);
+}
+
+#[test]
+fn test_interpolation() {
+	let source = r#"let values = [1]; let s = "s"; let text = "{values} and {1u8} {2.5 > 1.0} {s}";"#;
+	let errors = type_errors(source);
+	assert_eq!(errors, vec![
+		(String::from("only integers, floats, bools and strings can be put in a string, found `[i32]`"), (1, 45))
+	]);
+}